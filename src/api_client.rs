@@ -1,15 +1,33 @@
 use async_recursion::async_recursion;
 use serde_json::json;
-use std::io::{Error, ErrorKind};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Mutex;
 
 use crate::consts::SHC_BACKEND_API_BASE_URL;
-use crate::models::{AddFileResponse, RefreshTokenResponse, ShcFile, ShcFileResponse};
+use crate::filters::FileFilters;
+use crate::models::{
+    AddFileResponse, FileGrant, FileGrantResponse, RefreshTokenResponse, ShareLink,
+    ShareLinkResponse, ShcFile, ShcFileResponse, SnippetSource,
+};
+use crate::preferences::Preferences;
+use crate::rate_limit::RateLimiter;
 use crate::user_config::UserConfig;
 
+/// Pages fetched at most when filtering on the client, so a huge account doesn't hang `list`.
+const MAX_FILTERED_PAGES: u64 = 50;
+
+/// Methods take `&self` so several requests can run concurrently,
+/// the config sits behind a mutex because a request may refresh the tokens.
 pub struct ApiClient {
     api_base_url: String,
-    tried_refreshing_token: bool,
-    user_config: UserConfig,
+    tried_refreshing_token: AtomicBool,
+    user_config: Mutex<UserConfig>,
+    preferences: Preferences,
+    keystore_path: PathBuf,
+    /// shared by every upload and download
+    rate_limiter: Option<RateLimiter>,
     client: reqwest::Client,
 }
 
@@ -17,24 +35,68 @@ impl ApiClient {
     pub fn new(user_config: UserConfig) -> ApiClient {
         ApiClient {
             api_base_url: SHC_BACKEND_API_BASE_URL.to_string(),
-            tried_refreshing_token: false,
-            user_config,
+            tried_refreshing_token: AtomicBool::new(false),
+            preferences: user_config.preferences.clone(),
+            keystore_path: user_config.keystore_path(),
+            rate_limiter: user_config.preferences.limit_rate().map(RateLimiter::new),
+            user_config: Mutex::new(user_config),
             client: reqwest::Client::new(),
         }
     }
 
-    pub fn login_again(&mut self) {
-        self.tried_refreshing_token = true;
-        println!("Logged out, please login again");
-        // TODO: logout - clear config
+    pub fn preferences(&self) -> &Preferences {
+        &self.preferences
+    }
+
+    pub fn keystore_path(&self) -> &Path {
+        &self.keystore_path
+    }
+
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Overrides the configured limit, 0 removes it.
+    pub fn set_limit_rate(&mut self, bytes_per_sec: u64) {
+        self.rate_limiter = (bytes_per_sec > 0).then(|| RateLimiter::new(bytes_per_sec));
+    }
+
+    async fn access_token(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let user_config = self.user_config.lock().await;
+        user_config
+            .user
+            .access_token
+            .clone()
+            .ok_or_else(|| Error::other("Not logged in, please login again").into())
+    }
+
+    /// Whether the session couldn't be refreshed, every request fails from then on.
+    pub fn is_logged_out(&self) -> bool {
+        self.tried_refreshing_token.load(Ordering::SeqCst)
+    }
+
+    /// Error ending the command once the session can't be refreshed. It's returned
+    /// rather than exiting so callers like `shc ui` can restore the terminal first.
+    pub fn login_again(&self) -> Box<dyn std::error::Error> {
+        self.tried_refreshing_token.store(true, Ordering::SeqCst);
         // TODO: run login command (can we continue after login command?)
-        std::process::exit(1);
+        Error::other("Logged out, please login again").into()
     }
 
-    async fn refresh_token(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.tried_refreshing_token {
-            self.user_config.clear();
-            self.login_again();
+    /// Refreshes the tokens after `stale_access_token` was rejected,
+    /// unless a concurrent request already did.
+    async fn refresh_token(
+        &self,
+        stale_access_token: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut user_config = self.user_config.lock().await;
+        if user_config.user.access_token.as_deref() != Some(stale_access_token) {
+            return Ok(());
+        }
+
+        if self.tried_refreshing_token.load(Ordering::SeqCst) {
+            user_config.clear()?;
+            return Err(self.login_again());
         }
 
         let res = self
@@ -42,7 +104,7 @@ impl ApiClient {
             .get(format!("{}/auth/refresh-token", self.api_base_url))
             .header(
                 "Authorization",
-                self.user_config.user.refresh_token.as_ref().unwrap(),
+                user_config.user.refresh_token.as_ref().unwrap(),
             )
             .send()
             .await?;
@@ -50,60 +112,118 @@ impl ApiClient {
         match res.status() {
             reqwest::StatusCode::OK => match res.json::<RefreshTokenResponse>().await {
                 Ok(res) => {
-                    self.user_config.user.email = Some(res.user.email);
-                    self.user_config.user.name = Some(res.user.name);
-                    self.user_config.user.user_id = Some(res.user.id);
-                    self.user_config.user.access_token = Some(res.access_token);
-                    self.user_config.user.refresh_token = Some(res.refresh_token);
-                    self.user_config.save();
+                    user_config.user.email = Some(res.user.email);
+                    user_config.user.name = Some(res.user.name);
+                    user_config.user.user_id = Some(res.user.id);
+                    user_config.user.access_token = Some(res.access_token);
+                    user_config.user.refresh_token = Some(res.refresh_token);
+                    user_config.save()?;
                 }
                 Err(e) => {
                     return Err(e.into());
                 }
             },
             _ => {
-                self.login_again();
+                return Err(self.login_again());
             }
         }
         Ok(())
     }
 
-    #[async_recursion]
     pub async fn list_files(
-        &mut self,
+        &self,
+        search: &str,
+    ) -> Result<ShcFileResponse, Box<dyn std::error::Error>> {
+        self.list_filtered_files(search, &FileFilters::default())
+            .await
+    }
+
+    pub async fn list_filtered_files(
+        &self,
         search: &str,
+        filters: &FileFilters,
     ) -> Result<ShcFileResponse, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        self.list_files_at("/api/files", search, filters).await
+    }
+
+    pub async fn list_shared_files(
+        &self,
+        search: &str,
+        filters: &FileFilters,
+    ) -> Result<ShcFileResponse, Box<dyn std::error::Error>> {
+        self.list_files_at("/api/files/shared-with-me", search, filters)
+            .await
+    }
+
+    /// The first page of files at `path`. With filters it's every page instead,
+    /// they're applied here too and would otherwise only see the newest files.
+    async fn list_files_at(
+        &self,
+        path: &str,
+        search: &str,
+        filters: &FileFilters,
+    ) -> Result<ShcFileResponse, Box<dyn std::error::Error>> {
+        let mut res = self.list_files_page(path, search, filters, 1).await?;
+        if !filters.is_empty() {
+            let mut pages = 1;
+            while let Some(page) = res.next_page {
+                if pages == MAX_FILTERED_PAGES {
+                    eprintln!(
+                        "Only the newest {} files were filtered, narrow down the search to see the rest",
+                        res.results.len()
+                    );
+                    break;
+                }
+                let mut next = self.list_files_page(path, search, filters, page).await?;
+                res.results.append(&mut next.results);
+                res.next_page = next.next_page;
+                res.current_page = next.current_page;
+                pages += 1;
+            }
+        }
+        filters.apply(&mut res.results);
+        Ok(res)
+    }
+
+    #[async_recursion]
+    async fn list_files_page(
+        &self,
+        path: &str,
+        search: &str,
+        filters: &FileFilters,
+        page: u64,
+    ) -> Result<ShcFileResponse, Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
-            .get(format!(
-                "{}/api/files?search={}&page=1&limit=100",
-                self.api_base_url, search
-            ))
-            .header("Authorization", access_token)
+            .get(format!("{}{}", self.api_base_url, path))
+            .query(&[
+                ("search", search.to_string()),
+                ("page", page.to_string()),
+                ("limit", self.preferences.page_size().to_string()),
+            ])
+            .query(&filters.query())
+            .header("Authorization", &access_token)
             .send()
             .await?;
 
         match res.status() {
-            reqwest::StatusCode::OK => {
-                let res = res.json::<ShcFileResponse>().await?;
-                Ok(res)
-            }
+            reqwest::StatusCode::OK => Ok(res.json::<ShcFileResponse>().await?),
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.list_files(search).await;
+                self.refresh_token(&access_token).await?;
+                return self.list_files_page(path, search, filters, page).await;
             }
             _ => {
                 // TODO: use server error message
-                Err(Error::new(ErrorKind::Other, "Something went wrong").into())
+                Err(Error::other("Something went wrong").into())
             }
         }
     }
 
     #[async_recursion]
-    pub async fn remove_file(&mut self, file_id: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+    pub async fn remove_file(&self, file_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
@@ -111,26 +231,26 @@ impl ApiClient {
                 "{}/api/files/remove/{}",
                 self.api_base_url, file_id
             ))
-            .header("Authorization", access_token)
+            .header("Authorization", &access_token)
             .send()
             .await?;
 
         match res.status() {
             reqwest::StatusCode::OK => Ok(()),
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
+                self.refresh_token(&access_token).await?;
                 return self.remove_file(file_id).await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 
     #[async_recursion]
     pub async fn toggle_file_visibility(
-        &mut self,
+        &self,
         file_id: &str,
     ) -> Result<ShcFile, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
@@ -138,7 +258,7 @@ impl ApiClient {
                 "{}/api/files/toggle-visibility/{}",
                 self.api_base_url, file_id
             ))
-            .header("Authorization", access_token)
+            .header("Authorization", &access_token)
             .send()
             .await?;
 
@@ -148,20 +268,36 @@ impl ApiClient {
                 Ok(res)
             }
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
+                self.refresh_token(&access_token).await?;
                 return self.toggle_file_visibility(file_id).await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 
+    /// Makes the file public or private, only toggling when it isn't already.
+    /// Returns the updated file and whether anything changed.
+    pub async fn set_file_visibility(
+        &self,
+        file_id: &str,
+        is_public: bool,
+    ) -> Result<(ShcFile, bool), Box<dyn std::error::Error>> {
+        let file = self.get_file_download_url(file_id).await?;
+        if file.is_public == is_public {
+            return Ok((file, false));
+        }
+
+        let file = self.toggle_file_visibility(file_id).await?;
+        Ok((file, true))
+    }
+
     #[async_recursion]
     pub async fn rename_file(
-        &mut self,
+        &self,
         file_id: &str,
         new_name: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
@@ -169,7 +305,7 @@ impl ApiClient {
                 "{}/api/files/rename/{}",
                 self.api_base_url, file_id
             ))
-            .header("Authorization", access_token)
+            .header("Authorization", &access_token)
             .json(&json!({
                 "name": new_name,
             }))
@@ -179,31 +315,37 @@ impl ApiClient {
         match res.status() {
             reqwest::StatusCode::OK => Ok(()),
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
+                self.refresh_token(&access_token).await?;
                 return self.rename_file(file_id, new_name).await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 
     #[async_recursion]
     pub async fn add_file(
-        &mut self,
+        &self,
         file_name: &str,
         mime_type: &str,
         file_size: u64,
+        is_public: bool,
+        snippet: Option<&'async_recursion SnippetSource>,
+        encrypted: bool,
     ) -> Result<AddFileResponse, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
             .post(format!("{}/api/files/add", self.api_base_url))
-            .header("Authorization", access_token)
+            .header("Authorization", &access_token)
             .json(&json!(
                 {
                     "file_name": file_name,
                     "mime_type": mime_type,
                     "file_size": file_size,
+                    "is_public": is_public,
+                    "snippet": snippet,
+                    "encrypted": encrypted,
                 }
             ))
             .send()
@@ -215,20 +357,35 @@ impl ApiClient {
                 Ok(res)
             }
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.add_file(file_name, mime_type, file_size).await;
+                self.refresh_token(&access_token).await?;
+                return self
+                    .add_file(
+                        file_name, mime_type, file_size, is_public, snippet, encrypted,
+                    )
+                    .await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 
     #[async_recursion]
+    /// `checksum` is the hex SHA-256 of the uploaded content, sent once it's uploaded.
     pub async fn update_upload_status(
-        &mut self,
+        &self,
         file_id: &str,
         upload_status: &str,
+        checksum: Option<&'async_recursion str>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        let access_token = self.access_token().await?;
+
+        let mut body = json!(
+            {
+                "upload_status": upload_status,
+            }
+        );
+        if let Some(checksum) = checksum {
+            body["checksum"] = json!(checksum);
+        }
 
         let res = self
             .client
@@ -236,36 +393,34 @@ impl ApiClient {
                 "{}/api/files/update-upload-status/{}",
                 self.api_base_url, file_id
             ))
-            .json(&json!(
-                {
-                    "upload_status": upload_status,
-                }
-            ))
-            .header("Authorization", access_token)
+            .json(&body)
+            .header("Authorization", &access_token)
             .send()
             .await?;
 
         match res.status() {
             reqwest::StatusCode::OK => Ok(()),
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.update_upload_status(file_id, upload_status).await;
+                self.refresh_token(&access_token).await?;
+                return self
+                    .update_upload_status(file_id, upload_status, checksum)
+                    .await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 
     #[async_recursion]
     pub async fn get_file_download_url(
-        &mut self,
+        &self,
         file_id: &str,
     ) -> Result<ShcFile, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
             .get(format!("{}/api/files/{}", self.api_base_url, file_id))
-            .header("Authorization", access_token)
+            .header("Authorization", &access_token)
             .send()
             .await?;
 
@@ -275,19 +430,19 @@ impl ApiClient {
                 Ok(res)
             }
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
+                self.refresh_token(&access_token).await?;
                 return self.get_file_download_url(file_id).await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 
     #[async_recursion]
     pub async fn increment_download_count(
-        &mut self,
+        &self,
         file_id: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        let access_token = self.access_token().await?;
 
         let res = self
             .client
@@ -295,17 +450,205 @@ impl ApiClient {
                 "{}/api/files/increment-download-count/{}",
                 self.api_base_url, file_id
             ))
-            .header("Authorization", access_token)
+            .header("Authorization", &access_token)
             .send()
             .await?;
 
         match res.status() {
             reqwest::StatusCode::OK => Ok(()),
             reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
+                self.refresh_token(&access_token).await?;
                 return self.increment_download_count(file_id).await;
             }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
+            _ => Err(Error::other("Something went wrong").into()),
+        }
+    }
+
+    #[async_recursion]
+    pub async fn create_share_link(
+        &self,
+        file_id: &str,
+        expires_in: Option<u64>,
+        max_downloads: Option<u64>,
+        password: Option<&'async_recursion str>,
+    ) -> Result<ShareLink, Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
+
+        let res = self
+            .client
+            .post(format!("{}/api/share-links/create", self.api_base_url))
+            .header("Authorization", &access_token)
+            .json(&json!(
+                {
+                    "file_id": file_id,
+                    "expires_in": expires_in,
+                    "max_downloads": max_downloads,
+                    "password": password,
+                }
+            ))
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let res = res.json::<ShareLink>().await?;
+                Ok(res)
+            }
+            reqwest::StatusCode::UNAUTHORIZED => {
+                self.refresh_token(&access_token).await?;
+                return self
+                    .create_share_link(file_id, expires_in, max_downloads, password)
+                    .await;
+            }
+            _ => Err(Error::other("Something went wrong").into()),
+        }
+    }
+
+    #[async_recursion]
+    pub async fn list_share_links(
+        &self,
+        file_id: Option<&'async_recursion str>,
+    ) -> Result<ShareLinkResponse, Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
+
+        let res = self
+            .client
+            .get(format!(
+                "{}/api/share-links?file_id={}",
+                self.api_base_url,
+                file_id.unwrap_or_default()
+            ))
+            .header("Authorization", &access_token)
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let res = res.json::<ShareLinkResponse>().await?;
+                Ok(res)
+            }
+            reqwest::StatusCode::UNAUTHORIZED => {
+                self.refresh_token(&access_token).await?;
+                return self.list_share_links(file_id).await;
+            }
+            _ => Err(Error::other("Something went wrong").into()),
+        }
+    }
+
+    #[async_recursion]
+    pub async fn revoke_share_link(&self, link_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
+
+        let res = self
+            .client
+            .delete(format!(
+                "{}/api/share-links/revoke/{}",
+                self.api_base_url, link_id
+            ))
+            .header("Authorization", &access_token)
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => Ok(()),
+            reqwest::StatusCode::UNAUTHORIZED => {
+                self.refresh_token(&access_token).await?;
+                return self.revoke_share_link(link_id).await;
+            }
+            _ => Err(Error::other("Something went wrong").into()),
+        }
+    }
+
+    #[async_recursion]
+    pub async fn create_file_grant(
+        &self,
+        file_id: &str,
+        email: &str,
+        role: &str,
+    ) -> Result<FileGrant, Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
+
+        let res = self
+            .client
+            .post(format!("{}/api/files/grants/create", self.api_base_url))
+            .header("Authorization", &access_token)
+            .json(&json!(
+                {
+                    "file_id": file_id,
+                    "email": email,
+                    "role": role,
+                }
+            ))
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let res = res.json::<FileGrant>().await?;
+                Ok(res)
+            }
+            reqwest::StatusCode::UNAUTHORIZED => {
+                self.refresh_token(&access_token).await?;
+                return self.create_file_grant(file_id, email, role).await;
+            }
+            _ => Err(Error::other("Something went wrong").into()),
+        }
+    }
+
+    #[async_recursion]
+    pub async fn list_file_grants(
+        &self,
+        file_id: &str,
+    ) -> Result<FileGrantResponse, Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
+
+        let res = self
+            .client
+            .get(format!(
+                "{}/api/files/grants/{}",
+                self.api_base_url, file_id
+            ))
+            .header("Authorization", &access_token)
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let res = res.json::<FileGrantResponse>().await?;
+                Ok(res)
+            }
+            reqwest::StatusCode::UNAUTHORIZED => {
+                self.refresh_token(&access_token).await?;
+                return self.list_file_grants(file_id).await;
+            }
+            _ => Err(Error::other("Something went wrong").into()),
+        }
+    }
+
+    #[async_recursion]
+    pub async fn revoke_file_grant(
+        &self,
+        grant_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let access_token = self.access_token().await?;
+
+        let res = self
+            .client
+            .delete(format!(
+                "{}/api/files/grants/revoke/{}",
+                self.api_base_url, grant_id
+            ))
+            .header("Authorization", &access_token)
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => Ok(()),
+            reqwest::StatusCode::UNAUTHORIZED => {
+                self.refresh_token(&access_token).await?;
+                return self.revoke_file_grant(grant_id).await;
+            }
+            _ => Err(Error::other("Something went wrong").into()),
         }
     }
 }
@@ -1,4 +1,9 @@
-use clap::{arg, Command};
+use clap::{arg, Arg, ArgAction, Command};
+use regex::Regex;
+
+use crate::size::parse_size;
+use crate::snippet::parse_line_range;
+use crate::utils::{parse_date, parse_duration};
 
 pub fn cli() -> Command {
     Command::new("shc")
@@ -6,17 +11,106 @@ pub fn cli() -> Command {
         .subcommand_required(false)
         .arg_required_else_help(false)
         .allow_external_subcommands(true)
+        .arg(
+            arg!(-c --config <PATH> "config file to use (default: $SHC_CONFIG or ~/.shc-cli/config.toml)")
+                .global(true),
+        )
+        .arg(
+            arg!(--"limit-rate" <RATE> "maximum transfer speed per second, e.g. 2M or 500kB (0 for no limit)")
+                .value_parser(parse_size)
+                .global(true),
+        )
         .subcommand(Command::new("login").about("login to use shc"))
         .subcommand(
             Command::new("add")
-                .about("upload file")
-                .arg(arg!(<FILE> "file path to upload"))
+                .about("upload file, or a portion of it with FILE:20-45, --lines or --grep")
+                .arg(arg!([FILE] "file path to upload").required_unless_present("from-clipboard"))
+                .arg(arg!(--public "make the file public").conflicts_with("private"))
+                .arg(arg!(--private "make the file private"))
+                .arg(arg!(--copy "copy the link to the clipboard"))
+                .arg(arg!(--qr "print the link as a QR code"))
+                .arg(arg!(--"allow-secrets" "upload even if it looks like there are secrets in it"))
+                .arg(arg!(--"force-new" "upload even if the same file was uploaded already (folders always are)"))
+                .arg(arg!(--encrypt "encrypt the file before uploading, the key goes into the link"))
+                .arg(
+                    arg!(--"from-clipboard" "upload the text on the clipboard instead of a file")
+                        .conflicts_with_all(["FILE", "lines", "grep"]),
+                )
+                .arg(
+                    arg!(--lines <RANGE> "only upload these lines, e.g. 20-45, 20 or 20-")
+                        .value_parser(parse_line_range)
+                        .conflicts_with("grep"),
+                )
+                .arg(
+                    arg!(--grep <PATTERN> "only upload lines matching this regex")
+                        .value_parser(|pattern: &str| Regex::new(pattern).map_err(|e| e.to_string())),
+                )
+                .arg(
+                    arg!(-C --context <N> "lines of context around each --grep match")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0")
+                        .requires("grep"),
+                )
                 .arg_required_else_help(false),
         )
+        .subcommand(
+            Command::new("new")
+                .about("write a new text file in $EDITOR, or pipe it in, and upload it")
+                .arg(arg!(-n --name <NAME> "name of the file, e.g. notes.md"))
+                .arg(arg!(-l --lang <LANG> "language of the file, e.g. rust or py"))
+                .arg(arg!(--public "make the file public").conflicts_with("private"))
+                .arg(arg!(--private "make the file private"))
+                .arg(arg!(--copy "copy the link to the clipboard"))
+                .arg(arg!(--qr "print the link as a QR code"))
+                .arg(arg!(--"allow-secrets" "upload even if it looks like there are secrets in it"))
+                .arg(arg!(--"force-new" "upload even if the same file was uploaded already (folders always are)"))
+                .arg(arg!(--encrypt "encrypt the file before uploading, the key goes into the link")),
+        )
         .subcommand(
             Command::new("list")
                 .about("list all files")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .arg(arg!(<FILTER> "filter by filename").required(false))
+                .arg(arg!(--"shared-with-me" "list files other users shared with you"))
+                .arg(arg!(--table "print a table instead of picking a file (default when piped)"))
+                .arg(arg!(-l --long "print a table with every detail"))
+                .arg(
+                    Arg::new("names-only")
+                        .short('1')
+                        .help("print file names only, one per line")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--sort <FIELD> "sort files by")
+                        .value_parser(["name", "size", "updated", "downloads"]),
+                )
+                .arg(arg!(--reverse "reverse the sort order").requires("sort"))
+                .arg(arg!(--type <TYPE> "only files with this extension or mime type, e.g. rs or image/*"))
+                .arg(arg!(--public "only public files").conflicts_with("private"))
+                .arg(arg!(--private "only private files"))
+                .arg(arg!(--status <STATUS> "only files with this upload status, e.g. uploaded"))
+                .arg(
+                    arg!(--since <DATE> "only files updated since, e.g. 2024-01-31 or 7d")
+                        .value_parser(parse_date),
+                )
+                .arg(
+                    arg!(--before <DATE> "only files updated before, e.g. 2024-01-31 or 7d")
+                        .value_parser(parse_date),
+                )
+                .arg(
+                    arg!(--"min-size" <SIZE> "only files at least this big, e.g. 500kB or 50MiB")
+                        .value_parser(parse_size),
+                )
+                .arg(
+                    arg!(--"max-size" <SIZE> "only files at most this big, e.g. 2MB or 50MiB")
+                        .value_parser(parse_size),
+                )
+                .arg(arg!(--checksum <SHA256> "only files with this content hash")),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("show everything about a file")
+                .arg(arg!([FILTER] "filter by filename"))
+                .arg(arg!(--json "print the details as JSON")),
         )
         .subcommand(
             Command::new("remove")
@@ -25,18 +119,107 @@ pub fn cli() -> Command {
         )
         .subcommand(
             Command::new("visibility")
-                .about("toggle file's visibility")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .about("toggle file's visibility, or set it with --public/--private")
+                .arg(arg!([FILTER] ... "filter by filename, several with --public/--private"))
+                .arg(arg!(--public "make the files public").conflicts_with("private"))
+                .arg(arg!(--private "make the files private")),
         )
         .subcommand(
             Command::new("rename")
-                .about("rename file")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .about("rename file, or set the new name right away with NEW_NAME")
+                .arg(arg!(<FILTER> "filter by filename").required(false))
+                .arg(arg!([NEW_NAME] "new name of the file").requires("FILTER"))
+                .arg(arg!(-k --"keep-extension" "keep the original extension of the file")),
         )
         .subcommand(
             Command::new("get")
                 .about("download file")
+                .arg(arg!(<FILTER> "filter by filename, or a share link").required(false)),
+        )
+        .subcommand(
+            Command::new("link")
+                .about("print a file's share link and copy it to the clipboard")
                 .arg(arg!(<FILTER> "filter by filename").required(false)),
         )
-        .subcommand(Command::new("logout").about("logout from shc"))
+        .subcommand(
+            Command::new("qr")
+                .about("print a file's share link as a QR code")
+                .arg(arg!(<FILTER> "filter by filename").required(false)),
+        )
+        .subcommand(Command::new("ui").about("browse files in a full-screen interface"))
+        .subcommand(
+            Command::new("share")
+                .about("share a file through a scoped link or with specific users")
+                .args_conflicts_with_subcommands(true)
+                .arg(arg!([FILE] "filter by filename"))
+                .arg(
+                    arg!(--expires <DURATION> "link lifetime, e.g. 30m, 24h, 7d")
+                        .value_parser(parse_duration),
+                )
+                .arg(
+                    arg!(--"max-downloads" <COUNT> "downloads allowed before the link stops working")
+                        .value_parser(clap::value_parser!(u64).range(1..)),
+                )
+                .arg(arg!(--password "protect the link with a password"))
+                .arg(
+                    arg!(--with <EMAIL> "share with this user instead of creating a link")
+                        .action(ArgAction::Append)
+                        .conflicts_with_all(["expires", "max-downloads", "password"]),
+                )
+                .arg(
+                    arg!(--role <ROLE> "role of the users given with --with")
+                        .value_parser(["viewer", "editor"])
+                        .default_value("viewer")
+                        .requires("with"),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("list share links")
+                        .arg(arg!([FILTER] "only links of this file")),
+                )
+                .subcommand(
+                    Command::new("revoke")
+                        .about("revoke a share link")
+                        .arg(arg!([LINK] "link or link id to revoke")),
+                )
+                .subcommand(
+                    Command::new("access")
+                        .about("list who has access to a file")
+                        .arg(arg!([FILTER] "filter by filename")),
+                )
+                .subcommand(
+                    Command::new("revoke-access")
+                        .about("stop sharing a file with a user")
+                        .arg(arg!([FILTER] "filter by filename"))
+                        .arg(arg!(--with <EMAIL> "user to revoke")),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("manage preferences")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("get")
+                        .about("print a preference")
+                        .arg(arg!(<KEY> "preference key")),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("change a preference")
+                        .arg(arg!(<KEY> "preference key"))
+                        .arg(arg!(<VALUE> "new value")),
+                )
+                .subcommand(
+                    Command::new("unset")
+                        .about("reset a preference to its default")
+                        .arg(arg!(<KEY> "preference key")),
+                )
+                .subcommand(Command::new("list").about("list all preferences"))
+                .subcommand(Command::new("edit").about("edit preferences in $EDITOR")),
+        )
+        .subcommand(
+            Command::new("logout")
+                .about("logout from shc")
+                .arg(arg!(--"all-profiles" "logout from every stored profile")),
+        )
 }
@@ -0,0 +1,106 @@
+use base64::prelude::*;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+/// Native clipboard tools tried in order, with their arguments.
+const COPY_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+const PASTE_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbpaste", &[]),
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-o"]),
+    ("xsel", &["--clipboard", "--output"]),
+    (
+        "powershell.exe",
+        &["-NoProfile", "-Command", "Get-Clipboard"],
+    ),
+];
+
+fn is_ssh_session() -> bool {
+    std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+}
+
+fn copy_with_tool(command: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())?;
+    if child.wait()?.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} failed", command)))
+    }
+}
+
+/// Copies `text` through the OSC 52 escape sequence, which the terminal
+/// emulator handles, so it works over SSH too.
+fn copy_with_osc52(text: &str) -> io::Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text));
+    // stdout may be piped into another program, which shouldn't see the sequence
+    if io::stdout().is_terminal() {
+        let mut stdout = io::stdout();
+        stdout.write_all(sequence.as_bytes())?;
+        stdout.flush()
+    } else if io::stderr().is_terminal() {
+        io::stderr().write_all(sequence.as_bytes())
+    } else {
+        Err(io::Error::other(
+            "No clipboard tool or terminal to copy with",
+        ))
+    }
+}
+
+/// Copies `text` with the native clipboard tool when there is one. Over SSH,
+/// or when no tool works, it falls back to OSC 52.
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    if !is_ssh_session() {
+        for (command, args) in COPY_COMMANDS {
+            if copy_with_tool(command, args, text).is_ok() {
+                return Ok(());
+            }
+        }
+    }
+    copy_with_osc52(text)
+}
+
+/// Text currently on the clipboard. Terminals rarely allow reading it through
+/// OSC 52, so this needs a native clipboard tool.
+pub fn read_clipboard() -> io::Result<String> {
+    for (command, args) in PASTE_COMMANDS {
+        let mut child = match Command::new(command)
+            .args(*args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+
+        let mut text = String::new();
+        child
+            .stdout
+            .take()
+            .expect("stdout is piped")
+            .read_to_string(&mut text)?;
+        if child.wait()?.success() {
+            return Ok(text);
+        }
+    }
+    Err(io::Error::other(
+        "Cannot read the clipboard, install wl-clipboard, xclip or xsel",
+    ))
+}
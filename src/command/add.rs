@@ -1,23 +1,227 @@
+use console::style;
+use dialoguer::Confirm;
 use indicatif::{ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
 use std::cmp::min;
-use std::path::Path;
+use std::io::{Error, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio_stream::StreamExt;
 use tokio_util::io::ReaderStream;
 
 use crate::api_client;
+use crate::command::link::share_link;
+use crate::consts::SHC_SECRETS_FILE_NAME;
+use crate::crypto::{encrypt_file, FileKey};
+use crate::filters::FileFilters;
+use crate::keystore::Keystore;
+use crate::models::{ShcFile, SnippetSource};
+use crate::preferences::Visibility;
+use crate::secrets::SecretScanner;
+use crate::snippet::{cut_snippet, Selection};
+use crate::tui::{rate_limit_note, transfer_progress_style};
 use crate::utils::zip_directory_recursive;
 
-pub async fn upload_file(
+pub struct UploadOptions {
+    pub visibility: Visibility,
+    /// set when only a portion of a file is uploaded
+    pub snippet: Option<SnippetSource>,
+    /// upload even if the secret scanner finds something
+    pub allow_secrets: bool,
+    /// encrypt before uploading, the key only goes into the link
+    pub encrypt: bool,
+    /// upload even if a file with the same content was uploaded already
+    pub force_new: bool,
+    /// where the content came from when `upload_file` gets a temp copy of it,
+    /// the secret scanner treats the upload as that file
+    pub source_path: Option<PathBuf>,
+}
+
+impl UploadOptions {
+    pub fn new(visibility: Visibility) -> UploadOptions {
+        UploadOptions {
+            visibility,
+            snippet: None,
+            allow_secrets: false,
+            encrypt: false,
+            force_new: false,
+            source_path: None,
+        }
+    }
+}
+
+/// Uploads the selected lines of `file_path` as a file of their own.
+pub async fn upload_snippet(
     file_path: &Path,
+    display_path: &str,
+    selection: &Selection,
+    mut options: UploadOptions,
     api_client: &mut api_client::ApiClient,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let snippet = cut_snippet(file_path, display_path, selection)?;
+
+    // the snippet gets its own directory so the uploaded file keeps its name
+    let dir = tempfile::tempdir()?;
+    let snippet_path = dir.path().join(&snippet.file_name);
+    std::fs::write(&snippet_path, &snippet.content)?;
+
+    options.snippet = Some(snippet.source);
+    options.source_path = Some(file_path.to_path_buf());
+    upload_file(&snippet_path, options, api_client).await
+}
+
+/// Scans what's about to be uploaded and refuses to make secrets public.
+/// Private uploads only get a warning.
+fn check_secrets(
+    file_path: &Path,
+    options: &UploadOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = std::fs::canonicalize(file_path)?;
+    let source_path = match &options.source_path {
+        Some(source_path) => Some(std::path::absolute(source_path)?),
+        None => None,
+    };
+    let rule_path = source_path.as_deref().unwrap_or(&file_path);
+    let root = if rule_path.is_dir() {
+        rule_path
+    } else {
+        rule_path.parent().unwrap_or(rule_path)
+    };
+    let scanner = SecretScanner::new(root)?;
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Scanning for secrets...");
+    let findings = match &source_path {
+        Some(source_path) => scanner.scan_as(&file_path, source_path),
+        None => scanner.scan(&file_path),
+    };
+    pb.finish_and_clear();
+
+    if findings.is_empty() {
+        return Ok(());
+    }
+
+    println!("{}", style("Possible secrets found:").yellow().bold());
+    for finding in &findings {
+        let path = finding.path.strip_prefix(root).unwrap_or(&finding.path);
+        let location = match finding.line {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        println!(
+            "  {}  {}  {}",
+            location,
+            style(&finding.rule).yellow(),
+            finding.excerpt
+        );
+    }
+
+    if options.allow_secrets {
+        println!("Uploading anyway because of --allow-secrets");
+        return Ok(());
+    }
+    if options.visibility == Visibility::Public {
+        return Err(Error::other(format!(
+            "Refusing to upload secrets publicly. Remove them, allow the files in {} or pass --allow-secrets",
+            SHC_SECRETS_FILE_NAME
+        ))
+        .into());
+    }
+    println!("Uploading anyway because the file stays private");
+    Ok(())
+}
+
+/// Looks for an uploaded file with the same content and visibility as `file_path`.
+async fn find_duplicate(
+    file_path: &Path,
+    options: &UploadOptions,
+    api_client: &mut api_client::ApiClient,
+) -> Result<Option<ShcFile>, Box<dyn std::error::Error>> {
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Looking for earlier uploads...");
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(file_path)?, &mut hasher)?;
+    let checksum = format!("{:x}", hasher.finalize());
+
+    let filters = FileFilters {
+        checksum: Some(checksum),
+        is_public: Some(options.visibility == Visibility::Public),
+        status: Some("uploaded".to_string()),
+        ..Default::default()
+    };
+    let res = api_client.list_filtered_files("", &filters).await;
+    pb.finish_and_clear();
+    Ok(res?.results.into_iter().next())
+}
+
+/// Whether to hand out the link of `duplicate` instead of uploading again.
+/// Without a terminal to ask on, the link is reused.
+fn reuse_duplicate(duplicate: &ShcFile, link: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    println!(
+        "\"{}\" has the same content and was uploaded already: {}",
+        duplicate.name, link
+    );
+    if !std::io::stdin().is_terminal() {
+        println!("Reusing its link, pass --force-new to upload anyway");
+        return Ok(true);
+    }
+    Ok(Confirm::new()
+        .with_prompt("Reuse its link?")
+        .default(true)
+        .interact()?)
+}
+
+fn save_key(
+    file_id: &str,
+    key: &FileKey,
+    keystore_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut keystore = Keystore::load(keystore_path)?;
+    keystore.insert(file_id, key);
+    keystore.save()
+}
+
+/// Uploads `file_path`, zipping it first if it's a folder. Returns the share
+/// link once the file is added.
+pub async fn upload_file(
+    file_path: &Path,
+    options: UploadOptions,
+    api_client: &mut api_client::ApiClient,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
     if !file_path.exists() {
         println!("ShcFile or Folder does not exist");
-        return Ok(());
+        return Ok(None);
     }
 
+    check_secrets(file_path, &options)?;
+
+    // an encrypted upload is new ciphertext every time and a zipped folder is
+    // stamped with the time it was zipped, so neither ever has a duplicate
     let is_dir = file_path.is_dir();
+    if !options.force_new && !options.encrypt && !is_dir {
+        if let Some(duplicate) = find_duplicate(file_path, &options, api_client).await? {
+            let link = share_link(&duplicate.id, None);
+            if reuse_duplicate(&duplicate, &link)? {
+                return Ok(Some(link));
+            }
+        }
+    }
+
     let file_path = if is_dir {
         let pb = ProgressBar::new_spinner();
 
@@ -29,7 +233,12 @@ pub async fn upload_file(
         );
 
         pb.set_message("Compressing folder...");
-        let zip_file_path = zip_directory_recursive(file_path, 30 * 1024 * 1024)?;
+        let compression_method = api_client
+            .preferences()
+            .archive_format()
+            .compression_method();
+        let zip_file_path =
+            zip_directory_recursive(file_path, 30 * 1024 * 1024, compression_method)?;
         pb.finish_and_clear();
         zip_file_path
     } else {
@@ -37,8 +246,32 @@ pub async fn upload_file(
     };
 
     let file_name = file_path.file_name().unwrap().to_str().unwrap();
-    let mime_type = mime_guess::from_path(&file_path).first_or_octet_stream();
-    let file = tokio::fs::File::open(&file_path)
+    let key = options.encrypt.then(FileKey::generate);
+    // the encrypted copy has to outlive the upload
+    let encrypted_dir;
+    let (upload_path, mime_type) = match &key {
+        Some(key) => {
+            let pb = ProgressBar::new_spinner();
+
+            pb.enable_steady_tick(Duration::from_millis(200));
+            pb.set_style(
+                ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+                    .unwrap()
+                    .tick_chars("/|\\- "),
+            );
+            pb.set_message("Encrypting...");
+            encrypted_dir = tempfile::tempdir()?;
+            let encrypted_path = encrypted_dir.path().join(file_name);
+            encrypt_file(&file_path, &encrypted_path, key)?;
+            pb.finish_and_clear();
+            (encrypted_path, mime_guess::mime::APPLICATION_OCTET_STREAM)
+        }
+        None => (
+            file_path.clone(),
+            mime_guess::from_path(&file_path).first_or_octet_stream(),
+        ),
+    };
+    let file = tokio::fs::File::open(&upload_path)
         .await
         .expect("Cannot open input file for HTTPS read");
     let total_size = file
@@ -59,27 +292,34 @@ pub async fn upload_file(
 
     pb.set_message("Preparing for upload...");
 
+    let wants_public = options.visibility == Visibility::Public;
     let res = api_client
-        .add_file(file_name, mime_type.as_ref(), total_size)
+        .add_file(
+            file_name,
+            mime_type.as_ref(),
+            total_size,
+            wants_public,
+            options.snippet.as_ref(),
+            key.is_some(),
+        )
         .await?;
     pb.finish_and_clear();
 
     let file_id = res.file_id;
     let file_name = res.file_name;
     let upload_url = res.upload_url;
+    let is_public = res.is_public;
 
     let mut uploaded = 0;
 
     let mut reader_stream = ReaderStream::new(file);
     let bar = ProgressBar::new(total_size);
-    bar.set_style(
-        ProgressStyle::with_template(
-            "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec} \n",
-        )
-        .unwrap()
-        .progress_chars("#>-"),
-    );
-    let res = api_client.update_upload_status(&file_id, "uploading").await;
+    bar.set_style(transfer_progress_style(
+        api_client.preferences().size_format(),
+    ));
+    let res = api_client
+        .update_upload_status(&file_id, "uploading", None)
+        .await;
 
     match res {
         Ok(_) => {}
@@ -91,10 +331,25 @@ pub async fn upload_file(
     }
 
     bar.reset_eta();
-    bar.set_message(format!("Uploading {}", file_name));
+    let rate_limiter = api_client.rate_limiter().cloned();
+    bar.set_message(format!(
+        "Uploading {}{}",
+        file_name,
+        rate_limit_note(
+            rate_limiter.as_ref(),
+            api_client.preferences().size_format()
+        )
+    ));
+    // hashed on the way out so the file is read only once
+    let hasher = Arc::new(Mutex::new(Sha256::new()));
+    let stream_hasher = hasher.clone();
     let async_stream = async_stream::stream! {
         while let Some(chunk) = reader_stream.next().await {
             if let Ok(chunk) = &chunk {
+                if let Some(rate_limiter) = &rate_limiter {
+                    rate_limiter.acquire(chunk.len() as u64).await;
+                }
+                stream_hasher.lock().unwrap().update(chunk);
                 let new = min(uploaded + (chunk.len() as u64), total_size);
                 uploaded = new;
                 bar.set_position(new);
@@ -126,16 +381,37 @@ pub async fn upload_file(
 
     pb.set_message("Adding file...");
 
+    let mut link = None;
     match res.status() {
         reqwest::StatusCode::OK => {
-            let res = api_client.update_upload_status(&file_id, "uploaded").await;
+            let checksum = format!("{:x}", hasher.lock().unwrap().clone().finalize());
+            let mut res = api_client
+                .update_upload_status(&file_id, "uploaded", Some(&checksum))
+                .await;
+            // older backends ignore `is_public` on add, so fix it up afterwards
+            if res.is_ok() && is_public != wants_public {
+                res = api_client
+                    .toggle_file_visibility(&file_id)
+                    .await
+                    .map(|_| ());
+            }
             pb.finish_and_clear();
             match res {
                 Ok(_) => {
+                    let shareable_link = share_link(&file_id, key.as_ref());
+                    if let Some(key) = &key {
+                        if let Err(e) = save_key(&file_id, key, api_client.keystore_path()) {
+                            println!(
+                                "Could not save the key ({}), only the link below can decrypt the file",
+                                e
+                            );
+                        }
+                    }
                     print!(
-                "\n{} added successfully\nShcFile Link: https://shc.ajaysharma.dev/share/{}\n",
-                file_name, file_id
-            );
+                        "\n{} added successfully\nShcFile Link: {}\n",
+                        file_name, shareable_link
+                    );
+                    link = Some(shareable_link);
                 }
                 Err(_) => {
                     print!("Failed to add file");
@@ -144,7 +420,7 @@ pub async fn upload_file(
         }
         _ => {
             let res = api_client
-                .update_upload_status(file_id.as_str(), "failed")
+                .update_upload_status(file_id.as_str(), "failed", None)
                 .await;
             pb.finish_and_clear();
             match res {
@@ -164,5 +440,5 @@ pub async fn upload_file(
         std::fs::remove_file(&file_path)?;
     }
 
-    Ok(())
+    Ok(link)
 }
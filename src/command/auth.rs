@@ -84,7 +84,7 @@ pub async fn login(user_config: &mut UserConfig) -> Result<(), Box<dyn std::erro
         user_config.user.user_id = Some(res.id);
         user_config.user.access_token = Some(res.access_token);
         user_config.user.refresh_token = Some(res.refresh_token);
-        user_config.save();
+        user_config.save()?;
     } else {
         println!("Login Failed");
     }
@@ -104,7 +104,84 @@ pub async fn check_for_api_key(
     Ok(())
 }
 
-pub fn logout(user_config: &mut UserConfig) {
-    user_config.clear();
+async fn revoke_session(refresh_token: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post(format!("{}/auth/logout", consts::SHC_BACKEND_API_BASE_URL))
+        .header("Authorization", refresh_token)
+        .send()
+        .await?;
+
+    match res.status() {
+        // an already expired or revoked token means the session is gone anyway
+        reqwest::StatusCode::OK | reqwest::StatusCode::UNAUTHORIZED => Ok(()),
+        _ => Err(std::io::Error::other("Something went wrong").into()),
+    }
+}
+
+async fn logout_profile(user_config: &mut UserConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = user_config.config_path.display().to_string();
+    if !user_config.user.has_credentials() {
+        println!("No credentials stored in {}", config_path);
+        return Ok(());
+    }
+
+    if let Some(refresh_token) = user_config.user.refresh_token.as_ref() {
+        let pb = ProgressBar::new_spinner();
+
+        pb.enable_steady_tick(Duration::from_millis(200));
+        pb.set_style(
+            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+                .unwrap()
+                .tick_chars("/|\\- "),
+        );
+        pb.set_message("Revoking session...");
+        let res = revoke_session(refresh_token).await;
+        pb.finish_and_clear();
+
+        match res {
+            Ok(_) => println!("Session revoked on server"),
+            Err(e) => println!("Could not revoke session on server: {}", e),
+        }
+    }
+
+    let removed = user_config.clear()?;
+    let had_account_info =
+        removed.email.is_some() || removed.name.is_some() || removed.user_id.is_some();
+    let account = removed
+        .email
+        .or(removed.name)
+        .unwrap_or_else(|| "unknown account".to_string());
+    let mut fields = vec![];
+    if removed.access_token.is_some() {
+        fields.push("access token");
+    }
+    if removed.refresh_token.is_some() {
+        fields.push("refresh token");
+    }
+    if had_account_info {
+        fields.push("account info");
+    }
+    println!(
+        "Removed {} of {} from {}",
+        fields.join(", "),
+        account,
+        config_path
+    );
+    Ok(())
+}
+
+pub async fn logout(
+    user_config: &mut UserConfig,
+    all_profiles: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if all_profiles {
+        for mut profile in user_config.all_profiles()? {
+            logout_profile(&mut profile).await?;
+        }
+    } else {
+        logout_profile(user_config).await?;
+    }
     println!("Logged out");
+    Ok(())
 }
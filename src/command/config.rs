@@ -0,0 +1,98 @@
+use console::style;
+use dialoguer::Editor;
+
+use crate::preferences::{preference_schema, Preferences, PREFERENCES_SCHEMA};
+use crate::user_config::UserConfig;
+
+pub fn get_preference(
+    key: &str,
+    user_config: &UserConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match user_config.preferences.get(key)? {
+        Some(value) => println!("{}", value),
+        None => println!("{}", preference_schema(key)?.default),
+    }
+    Ok(())
+}
+
+pub fn set_preference(
+    key: &str,
+    value: &str,
+    user_config: &mut UserConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    user_config.preferences.set(key, value)?;
+    user_config.save()?;
+    println!(
+        "{} set to {}",
+        key,
+        user_config.preferences.get(key)?.unwrap_or_default()
+    );
+    Ok(())
+}
+
+pub fn unset_preference(
+    key: &str,
+    user_config: &mut UserConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    user_config.preferences.unset(key)?;
+    user_config.save()?;
+    println!("{} reset to {}", key, preference_schema(key)?.default);
+    Ok(())
+}
+
+pub fn list_preferences(user_config: &UserConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let key_width = PREFERENCES_SCHEMA
+        .iter()
+        .map(|schema| schema.key.len())
+        .max()
+        .unwrap_or_default();
+
+    for schema in PREFERENCES_SCHEMA {
+        let value = match user_config.preferences.get(schema.key)? {
+            Some(value) => style(value).green().to_string(),
+            None => style(format!("{} (default)", schema.default))
+                .dim()
+                .to_string(),
+        };
+        println!(
+            "{:<key_width$}  {}\n{:<key_width$}  {}",
+            style(schema.key).cyan(),
+            value,
+            "",
+            style(schema.description).dim(),
+            key_width = key_width
+        );
+    }
+    Ok(())
+}
+
+pub fn edit_preferences(user_config: &mut UserConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = toml::to_string(&user_config.preferences)?;
+    let help = PREFERENCES_SCHEMA
+        .iter()
+        .map(|schema| {
+            format!(
+                "# {} = {}  ({})",
+                schema.key, schema.default, schema.description
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let edited = match Editor::new()
+        .extension(".toml")
+        .edit(&format!("{}\n\n{}", help, contents))?
+    {
+        Some(edited) => edited,
+        None => {
+            println!("No changes made");
+            return Ok(());
+        }
+    };
+
+    let table = edited.parse::<toml::Table>()?;
+    user_config.preferences = Preferences::from_table(table)?;
+    user_config.save()?;
+    println!("Preferences saved to {}", user_config.config_path.display());
+    Ok(())
+}
@@ -1,13 +1,191 @@
-use dialoguer::Confirm;
-use indicatif::{ProgressBar, ProgressStyle};
+use futures::future::join_all;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
 use std::cmp::min;
-use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tempfile::NamedTempFile;
 use tokio_stream::StreamExt;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::command::rename::validate_filename;
+use crate::consts::{SHC_QUARANTINE_FOLDER_NAME, SHC_SHARE_BASE_URL};
+use crate::crypto::{decrypt_file, FileKey};
+use crate::keystore::Keystore;
+use crate::models::ShcFile;
+use crate::tui::{confirm_action, rate_limit_note, shc_file_multi_input, transfer_progress_style};
+use crate::utils::batch_result;
+
+/// Temp file in `download_dir` that gets the usual permissions of a download
+/// instead of a temp file's owner-only ones.
+fn download_temp_file(download_dir: &Path) -> std::io::Result<NamedTempFile> {
+    let file = NamedTempFile::new_in(download_dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.as_file()
+            .set_permissions(std::fs::Permissions::from_mode(0o644))?;
+    }
+    Ok(file)
+}
+
+/// Decrypts a download with `key`, or the key from the keystore, into a new temp file.
+fn decrypt_download(
+    download: &NamedTempFile,
+    download_dir: &Path,
+    file_id: &str,
+    key: Option<&FileKey>,
+    api_client: &api_client::ApiClient,
+) -> Result<NamedTempFile, Box<dyn std::error::Error>> {
+    let stored_key;
+    let key = match key {
+        Some(key) => key,
+        None => {
+            stored_key = Keystore::load(api_client.keystore_path())?.get(file_id);
+            stored_key.as_ref().ok_or_else(|| {
+                std::io::Error::other(
+                    "The file is encrypted and there's no key for it, get it with its share link",
+                )
+            })?
+        }
+    };
+
+    let decrypted = download_temp_file(download_dir)?;
+    decrypt_file(download.path(), decrypted.path(), key)?;
+    Ok(decrypted)
+}
+
+/// Keeps a download that failed verification for inspection, out of the way
+/// so it isn't mistaken for the real file.
+fn quarantine(
+    download: NamedTempFile,
+    download_dir: &Path,
+    file_name: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let quarantine_dir = download_dir.join(SHC_QUARANTINE_FOLDER_NAME);
+    std::fs::create_dir_all(&quarantine_dir)?;
+    let quarantined = quarantine_dir.join(format!(
+        "{}-{}",
+        chrono::Local::now().format("%Y%m%d%H%M%S"),
+        file_name
+    ));
+    download.persist(&quarantined)?;
+    Ok(quarantined)
+}
+
+/// Splits a share link like `https://shc.ajaysharma.dev/share/<id>#key=<key>`
+/// into the file id and the key of encrypted files.
+fn parse_share_link(link: &str) -> Option<(&str, Option<&str>)> {
+    let rest = link.strip_prefix(SHC_SHARE_BASE_URL)?.strip_prefix('/')?;
+    let (file_id, key) = match rest.split_once('#') {
+        Some((file_id, fragment)) => (file_id, fragment.strip_prefix("key=")),
+        None => (rest, None),
+    };
+    let file_id = file_id.trim_end_matches('/');
+    if file_id.is_empty() {
+        return None;
+    }
+    Some((file_id, key))
+}
+
+pub async fn download_shc_file(
+    file: &ShcFile,
+    download_dir: &Path,
+    client: &reqwest::Client,
+    multi_progress: &MultiProgress,
+    key: Option<&FileKey>,
+    api_client: &api_client::ApiClient,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let file_id = &file.id;
+    let pb = multi_progress.add(ProgressBar::new_spinner());
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message(format!("Preparing for download... {}", file.name));
+    let res = api_client.get_file_download_url(file_id).await;
+
+    pb.finish_and_clear();
+
+    let shc_file = res?;
+
+    let download_url = shc_file
+        .download_url
+        .ok_or_else(|| std::io::Error::other("No download url"))?;
+    // the name comes from the server, and with share links from another user,
+    // so it must not be able to point outside the download folder
+    let file_name = validate_filename(&shc_file.name)
+        .map_err(|e| format!("Refusing to save a file named {:?}: {}", shc_file.name, e))?;
+
+    let mut downloaded: u64 = 0;
+
+    let res = client.get(download_url).send().await?;
+    let total_size = downloaded + res.content_length().unwrap_or(0);
+    let bar = multi_progress.add(ProgressBar::new(total_size));
+    // written next to its final place and only moved there once it checks out,
+    // so a failed download never replaces or truncates a file
+    let mut download = download_temp_file(download_dir)
+        .map_err(|_| format!("Failed to create a file in '{}'", download_dir.display()))?;
+
+    let size_format = api_client.preferences().size_format();
+    let rate_limiter = api_client.rate_limiter();
+    bar.set_style(transfer_progress_style(size_format));
+    bar.reset_eta();
+    bar.set_message(format!(
+        "Downloading... {}{}",
+        file_name,
+        rate_limit_note(rate_limiter, size_format)
+    ));
+
+    let mut hasher = Sha256::new();
+    {
+        let mut out = std::io::BufWriter::new(download.as_file_mut());
+        let mut stream = res.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let chunk = item.map_err(|_| "Error while downloading.")?;
+            if let Some(rate_limiter) = rate_limiter {
+                rate_limiter.acquire(chunk.len() as u64).await;
+            }
+            hasher.update(&chunk);
+            out.write_all(&chunk)
+                .map_err(|_| "Error while writing to output.")?;
+            let new = min(downloaded + (chunk.len() as u64), total_size);
+            downloaded = new;
+            bar.set_position(new);
+        }
+        out.flush()?;
+    }
+    bar.finish_and_clear();
+
+    let checksum = format!("{:x}", hasher.finalize());
+    if let Some(expected) = &shc_file.checksum {
+        if !expected.eq_ignore_ascii_case(&checksum) {
+            let quarantined = quarantine(download, download_dir, &file_name)?;
+            return Err(format!(
+                "Checksum mismatch, expected sha256:{} but got sha256:{}. The download is corrupt and was moved to {}",
+                expected,
+                checksum,
+                quarantined.display()
+            )
+            .into());
+        }
+    }
+
+    if shc_file.encrypted || file.encrypted {
+        download = decrypt_download(&download, download_dir, file_id, key, api_client)?;
+    }
+    let file_path = download_dir.join(&file_name);
+    download.persist(&file_path)?;
+
+    // try to increment download count but ignore the result
+    let _ = api_client.increment_download_count(file_id).await;
+
+    Ok(file_path)
+}
 
 pub async fn download_file(
     search: &str,
@@ -23,92 +201,90 @@ pub async fn download_file(
             .unwrap()
             .tick_chars("/|\\- "),
     );
-    pb.set_message("Fetching files...");
-    let res = api_client.list_files(search).await?;
+    let (files, key) = match parse_share_link(search) {
+        Some((file_id, key)) => {
+            let key = key.map(FileKey::from_base64).transpose()?;
+            pb.set_message("Fetching file...");
+            let mut file = api_client.get_file_download_url(file_id).await?;
+            pb.finish_and_clear();
+            file.download_url = None;
+            (vec![file], key)
+        }
+        None => {
+            pb.set_message("Fetching files...");
+            let res = api_client.list_files(search).await?;
 
-    pb.finish_and_clear();
+            pb.finish_and_clear();
 
-    if res.results.is_empty() {
-        println!("No files found.");
-        return Ok(());
-    }
+            if res.results.is_empty() {
+                println!("No files found.");
+                return Ok(());
+            }
+
+            let selections = shc_file_multi_input(
+                &res.results,
+                "Which files do you want to download?",
+                api_client,
+            )?;
+            if selections.is_empty() {
+                println!("No files selected.");
+                return Ok(());
+            }
+            let files = res
+                .results
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| selections.contains(i))
+                .map(|(_, file)| file)
+                .collect::<Vec<ShcFile>>();
+            (files, None)
+        }
+    };
 
-    let selection = shc_file_input(&res.results, "Which file do you want to download?");
+    let download_dir = api_client.preferences().download_dir();
+    println!(
+        "Download {} file(s) to {}:",
+        files.len(),
+        download_dir.display()
+    );
+    for file in &files {
+        println!("  {}", file.name);
+    }
 
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure?")
-        .default(false)
-        .interact()
-        .unwrap();
+    let confirm = confirm_action(api_client.preferences())?;
 
     if !confirm {
         println!("Aborted");
         return Ok(());
     } else {
-        let file_id = res.results[selection].id.clone();
-        let pb = ProgressBar::new_spinner();
-
-        pb.enable_steady_tick(Duration::from_millis(200));
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                .unwrap()
-                .tick_chars("/|\\- "),
-        );
-        pb.set_message("Preparing for download...");
-        let res = api_client.get_file_download_url(&file_id).await;
-
-        pb.finish_and_clear();
-
-        let shc_file = match res {
-            Ok(shc_file) => shc_file,
-            Err(e) => {
-                println!("Error: {}", e);
-                std::process::exit(1);
+        std::fs::create_dir_all(&download_dir)?;
+        let multi_progress = MultiProgress::new();
+        let api_client = &*api_client;
+        let results = join_all(files.iter().map(|file| {
+            let download_dir = &download_dir;
+            let client = &client;
+            let multi_progress = &multi_progress;
+            let key = key.as_ref();
+            async move {
+                let res =
+                    download_shc_file(file, download_dir, client, multi_progress, key, api_client)
+                        .await;
+                (file, res)
             }
-        };
-
-        let download_url = shc_file.download_url;
-        let file_name = shc_file.name;
+        }))
+        .await;
 
-        let mut downloaded: u64 = 0;
-
-        let res = client.get(download_url.unwrap()).send().await?;
-        let total_size = downloaded + res.content_length().unwrap_or(0);
-        let bar = ProgressBar::new(total_size);
-        let file = File::create(&file_name)
-            .map_err(|_| format!("Failed to create file '{file_name}'"))
-            .unwrap();
-
-        let mut out: Box<dyn Write + Send> = Box::new(std::io::BufWriter::new(file));
-
-        bar.set_style(
-        ProgressStyle::with_template(
-            "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec} \n",
-        )
-        .unwrap()
-        .progress_chars("#>-"),
-    );
-        bar.reset_eta();
-        bar.set_message(format!("Downloading... {}", file_name));
-
-        let mut stream = res.bytes_stream();
-        while let Some(item) = stream.next().await {
-            let chunk = item.map_err(|_| "Error while downloading.").unwrap();
-            out.write_all(&chunk)
-                .map_err(|_| "Error while writing to output.")
-                .unwrap();
-            let new = min(downloaded + (chunk.len() as u64), total_size);
-            downloaded = new;
-            bar.set_position(new);
+        let mut failed = 0;
+        for (file, res) in results {
+            match res {
+                Ok(file_path) => println!("Downloaded {}", file_path.display()),
+                Err(e) => {
+                    failed += 1;
+                    println!("Error: {}: {}", file.name, e)
+                }
+            }
         }
-        bar.finish_and_clear();
-        println!("Downloaded {}", file_name);
-
-        // try to increment download count but ignore the result
-        match api_client.increment_download_count(&file_id).await {
-            Ok(_) => {}
-            Err(_) => {}
-        };
+        batch_result(failed, files.len(), "downloads")?;
     }
 
     Ok(())
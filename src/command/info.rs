@@ -0,0 +1,120 @@
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+use crate::api_client;
+use crate::command::link::{file_share_link, warn_missing_key};
+use crate::models::ShcFile;
+use crate::preferences::OutputFormat;
+use crate::tui::resolve_shc_file;
+
+pub fn print_file_info(
+    file: &ShcFile,
+    output_format: OutputFormat,
+    api_client: &api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let preferences = api_client.preferences();
+    if output_format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(file)?);
+        return Ok(());
+    }
+
+    let date_format = preferences.date_format();
+    let size_format = preferences.size_format();
+    let unknown = || "-".to_string();
+    let created_at = file
+        .created_at
+        .as_deref()
+        .map(|created_at| date_format.format_long(created_at))
+        .unwrap_or_else(unknown);
+    let updated_at = date_format.format_long(&file.updated_at);
+    let size = format!("{} ({} bytes)", size_format.format(file.size), file.size);
+    let visibility = if file.is_public { "Public" } else { "Private" };
+    let downloads = file
+        .download_count
+        .map(|count| count.to_string())
+        .unwrap_or_else(unknown);
+    let checksum = file
+        .checksum
+        .as_ref()
+        .map(|checksum| format!("sha256:{}", checksum))
+        .unwrap_or_else(unknown);
+    let owner = match &file.owner {
+        Some(owner) => format!("{} <{}>", owner.name, owner.email),
+        None => file.user_id.clone(),
+    };
+    let snippet_of = match &file.snippet {
+        Some(snippet) => {
+            let lines = snippet
+                .lines
+                .iter()
+                .map(|range| format!("{}-{}", range.start, range.end))
+                .collect::<Vec<String>>()
+                .join(",");
+            format!("{}:{}", snippet.path, lines)
+        }
+        None => unknown(),
+    };
+    let shareable_link = file_share_link(file, api_client.keystore_path()).unwrap_or_else(|link| {
+        warn_missing_key();
+        link
+    });
+
+    console::Term::stdout().write_line(
+        format!(
+            "\nFile Name: {}\nId: {}\nUpload Status: {}\nCreated At: {}\nUpdated At: {}\nSize: {}\nType: {}\nExtension: {}\nVisibility: {}\nEncrypted: {}\nDownloads: {}\nChecksum: {}\nOwner: {}\nSnippet Of: {}\nShareable Link: {}",
+            style(&file.name).cyan(),
+            file.id,
+            style(&file.upload_status).yellow(),
+            style(created_at).green(),
+            style(updated_at).green(),
+            style(size).magenta(),
+            file.mime_type,
+            file.extension,
+            style(visibility).blue(),
+            if file.encrypted { "Yes" } else { "No" },
+            downloads,
+            checksum,
+            owner,
+            snippet_of,
+            style(shareable_link).underlined().bright().blue()
+        )
+        .as_ref(),
+    )?;
+    Ok(())
+}
+
+pub async fn file_info(
+    search: &str,
+    json: bool,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = match resolve_shc_file(search, "Which file do you want to see?", api_client).await? {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Fetching file info...");
+    let res = api_client.get_file_download_url(&file.id).await;
+    pb.finish_and_clear();
+
+    // the list entry has everything but the details only the file endpoint returns
+    let mut file = res.unwrap_or(file);
+    // presigned urls expire quickly, there's no point in printing them
+    file.download_url = None;
+
+    let output_format = if json {
+        OutputFormat::Json
+    } else {
+        api_client.preferences().output_format()
+    };
+    print_file_info(&file, output_format, api_client)
+}
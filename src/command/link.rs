@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use crate::api_client;
+use crate::clipboard::copy_to_clipboard;
+use crate::consts::SHC_SHARE_BASE_URL;
+use crate::crypto::FileKey;
+use crate::keystore::Keystore;
+use crate::models::ShcFile;
+use crate::tui::resolve_shc_file;
+
+/// Link to `file_id`, with the key in the fragment for encrypted files.
+pub fn share_link(file_id: &str, key: Option<&FileKey>) -> String {
+    match key {
+        Some(key) => format!("{}/{}#key={}", SHC_SHARE_BASE_URL, file_id, key.to_base64()),
+        None => format!("{}/{}", SHC_SHARE_BASE_URL, file_id),
+    }
+}
+
+/// Link to an uploaded file that whoever gets it can open. `Err` carries the
+/// bare link of an encrypted file whose key isn't in the keystore.
+pub fn file_share_link(file: &ShcFile, keystore_path: &Path) -> Result<String, String> {
+    if !file.encrypted {
+        return Ok(share_link(&file.id, None));
+    }
+    match Keystore::load(keystore_path)
+        .ok()
+        .and_then(|keystore| keystore.get(&file.id))
+    {
+        Some(key) => Ok(share_link(&file.id, Some(&key))),
+        None => Err(share_link(&file.id, None)),
+    }
+}
+
+/// Tells the user a link can't decrypt the file it points to.
+pub fn warn_missing_key() {
+    eprintln!("No key is stored for this encrypted file, the link can't decrypt it");
+}
+
+/// Copies `link` and tells the user whether that worked, without failing the command.
+pub fn copy_link(link: &str) {
+    match copy_to_clipboard(link) {
+        Ok(_) => eprintln!("Link copied to clipboard"),
+        Err(e) => eprintln!("Could not copy the link: {}", e),
+    }
+}
+
+/// Prints the share link of a file and copies it.
+pub async fn file_link(
+    search: &str,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = match resolve_shc_file(search, "Which file's link do you want?", api_client).await? {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+
+    let link = file_share_link(&file, api_client.keystore_path()).unwrap_or_else(|link| {
+        warn_missing_key();
+        link
+    });
+    println!("{}", link);
+    copy_link(&link);
+    Ok(())
+}
@@ -1,13 +1,29 @@
-use chrono::{DateTime, Utc};
-use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::command::info::print_file_info;
+use crate::filters::FileFilters;
+use crate::models::ShcFile;
+use crate::preferences::{Column, OutputFormat};
+use crate::tui::{shc_file_input, FileTable};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ListOutput {
+    /// pick a file and show its details
+    Interactive,
+    Table,
+    /// table with every detail column
+    Long,
+    /// one name per line
+    Names,
+}
 
 pub async fn list_files(
     search: &str,
+    shared_with_me: bool,
+    filters: &FileFilters,
+    output: ListOutput,
     api_client: &mut api_client::ApiClient,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let pb = ProgressBar::new_spinner();
@@ -21,48 +37,85 @@ pub async fn list_files(
 
     pb.set_message("Fetching files...");
 
-    let res = api_client.list_files(search).await?;
+    let res = if shared_with_me {
+        api_client.list_shared_files(search, filters).await?
+    } else {
+        api_client.list_filtered_files(search, filters).await?
+    };
     pb.finish_and_clear();
 
+    if output != ListOutput::Interactive {
+        print_files(&res.results, output, api_client)?;
+        return Ok(());
+    }
+
     if res.results.is_empty() {
         println!("No files found.");
         return Ok(());
     }
 
     let file_count = res.results.len();
-    let prompt = if file_count > 100 {
-        "Select a file to see more info. (Last 100 files, use filter to get more specific results)"
-            .to_string()
+    let page_size = api_client.preferences().page_size() as usize;
+    let prompt = if file_count >= page_size {
+        format!(
+            "Select a file to see more info. (Last {} files, use filter to get more specific results)",
+            page_size
+        )
     } else {
         format!("Select a file to see more info.  ({} files)", file_count)
     };
 
-    let selection = shc_file_input(&res.results, &prompt);
+    let mut files = res.results;
+    let selection = shc_file_input(&mut files, &prompt, api_client).await?;
 
-    let file = &res.results[selection];
-    let file_name = &file.name;
-    let upload_status = &file.upload_status;
-    let updated_at = DateTime::<Utc>::from(DateTime::parse_from_rfc3339(&file.updated_at)?)
-        .format("%Y-%m-%d %H:%M:%S")
-        .to_string();
-    let size = if file.size < 1024 {
-        format!("{:.3} KB", file.size as f64 / 1024.0)
-    } else {
-        format!("{:.3} MB", file.size as f64 / 1024.0 / 1024.0)
-    };
-    let visibility = if file.is_public { "Public" } else { "Private" };
-    let shareable_link = format!("https://shc.ajaysharma.dev/share/{}", file.id);
+    print_file_info(
+        &files[selection],
+        api_client.preferences().output_format(),
+        api_client,
+    )
+}
+
+fn print_files(
+    files: &[ShcFile],
+    output: ListOutput,
+    api_client: &api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let preferences = api_client.preferences();
+    if output == ListOutput::Names {
+        for file in files {
+            println!("{}", file.name);
+        }
+        return Ok(());
+    }
 
-    console::Term::stdout()
-        .write_line( format!(
-        "\nFile Name: {}\nUpload Status: {}\nUpdated At: {}\nSize: {}\nVisibility: {}\nShareable Link: {}",
-        style(file_name).cyan(),
-        style(upload_status).yellow(),
-        style(updated_at).green(),
-        style(size).magenta(),
-        style(visibility).blue(),
-        style(shareable_link).underlined().bright().blue()
-    ).as_ref())?;
+    if preferences.output_format() == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(files)?);
+        return Ok(());
+    }
 
+    if files.is_empty() {
+        eprintln!("No files found.");
+        return Ok(());
+    }
+
+    let columns = if output == ListOutput::Long {
+        vec![
+            Column::Name,
+            Column::Size,
+            Column::Updated,
+            Column::Visibility,
+            Column::Status,
+            Column::Downloads,
+            Column::Type,
+            Column::Link,
+        ]
+    } else {
+        preferences.columns()
+    };
+    let table = FileTable::fit_content(files, columns, api_client);
+    println!("{}", table.header());
+    for file in files {
+        println!("{}", table.row(file));
+    }
     Ok(())
 }
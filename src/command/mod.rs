@@ -1,7 +1,14 @@
 pub mod add;
 pub mod auth;
+pub mod config;
 pub mod get;
+pub mod info;
+pub mod link;
 pub mod list;
+pub mod new;
+pub mod qr;
 pub mod remove;
 pub mod rename;
+pub mod share;
+pub mod ui;
 pub mod visibility;
@@ -0,0 +1,123 @@
+use dialoguer::Editor;
+use std::io::{Error, IsTerminal, Read};
+use std::path::Path;
+
+use crate::api_client;
+use crate::command::add::{upload_file, UploadOptions};
+use crate::command::rename::validate_filename;
+
+const LANGUAGE_EXTENSIONS: &[(&str, &str)] = &[
+    ("bash", "sh"),
+    ("c", "c"),
+    ("cpp", "cpp"),
+    ("csharp", "cs"),
+    ("css", "css"),
+    ("go", "go"),
+    ("html", "html"),
+    ("java", "java"),
+    ("javascript", "js"),
+    ("json", "json"),
+    ("kotlin", "kt"),
+    ("markdown", "md"),
+    ("python", "py"),
+    ("ruby", "rb"),
+    ("rust", "rs"),
+    ("shell", "sh"),
+    ("sql", "sql"),
+    ("swift", "swift"),
+    ("text", "txt"),
+    ("toml", "toml"),
+    ("typescript", "ts"),
+    ("yaml", "yml"),
+];
+
+/// Extension for `lang`, anything that isn't a known language is taken as an extension.
+fn language_extension(lang: &str) -> String {
+    let lang = lang.trim().trim_start_matches('.').to_lowercase();
+    LANGUAGE_EXTENSIONS
+        .iter()
+        .find(|(name, _)| *name == lang)
+        .map(|(_, extension)| extension.to_string())
+        .unwrap_or(lang)
+}
+
+/// `name`, or `<prefix>-<timestamp>` when there's none, with the extension of `lang`
+/// unless the name already has one.
+pub fn file_name(name: Option<&str>, prefix: &str, lang: Option<&str>) -> Result<String, Error> {
+    let extension = lang.map(language_extension);
+    let name = match name {
+        Some(name) => validate_filename(name).map_err(Error::other)?,
+        None => format!(
+            "{}-{}",
+            prefix,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ),
+    };
+
+    if Path::new(&name).extension().is_some() {
+        return Ok(name);
+    }
+    Ok(format!(
+        "{}.{}",
+        name,
+        extension.unwrap_or_else(|| "txt".to_string())
+    ))
+}
+
+/// Uploads text typed in `$EDITOR`, or piped through stdin, as a new file.
+pub async fn new_file(
+    name: Option<&str>,
+    lang: Option<&str>,
+    options: UploadOptions,
+    api_client: &mut api_client::ApiClient,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let file_name = file_name(name, "snippet", lang)?;
+
+    let content = if std::io::stdin().is_terminal() {
+        let extension = Path::new(&file_name)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("txt");
+        match Editor::new()
+            .extension(&format!(".{}", extension))
+            .require_save(true)
+            .edit("")?
+        {
+            Some(content) => content,
+            None => {
+                println!("Nothing saved, aborted");
+                return Ok(None);
+            }
+        }
+    } else {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        content
+    };
+
+    upload_text(&file_name, &content, options, api_client).await
+}
+
+/// Uploads `content` as a file called `file_name`.
+pub async fn upload_text(
+    file_name: &str,
+    content: &str,
+    options: UploadOptions,
+    api_client: &mut api_client::ApiClient,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if content.trim().is_empty() {
+        return Err(Error::other("Nothing to upload, the file is empty").into());
+    }
+
+    // the file gets its own directory so it's uploaded under the chosen name
+    let dir = tempfile::tempdir()?;
+    let file_path = dir.path().join(file_name);
+    std::fs::write(&file_path, content)?;
+
+    // scanned as if it was written in the current folder, whose rules apply
+    let options = UploadOptions {
+        source_path: Some(std::env::current_dir()?.join(file_name)),
+        ..options
+    };
+    upload_file(&file_path, options, api_client).await
+}
@@ -0,0 +1,86 @@
+use console::{style, Term};
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::api_client;
+use crate::command::link::{file_share_link, warn_missing_key};
+use crate::tui::resolve_shc_file;
+
+/// Light modules around the code, scanners need some to find it.
+const QUIET_ZONE: usize = 2;
+
+/// Renders `text` as a QR code with half blocks, two rows of modules per line.
+/// Lower error correction makes the code smaller, so it's only used when the
+/// sturdier one doesn't fit in `max_width` columns.
+fn render_qr(text: &str, max_width: usize) -> Option<String> {
+    let code = [EcLevel::M, EcLevel::L]
+        .into_iter()
+        .filter_map(|ec_level| QrCode::with_error_correction_level(text, ec_level).ok())
+        .find(|code| code.width() + 2 * QUIET_ZONE <= max_width)?;
+
+    let width = code.width();
+    let colors = code.to_colors();
+    let is_dark = |x: usize, y: usize| {
+        x >= QUIET_ZONE
+            && y >= QUIET_ZONE
+            && x < width + QUIET_ZONE
+            && y < width + QUIET_ZONE
+            && colors[(y - QUIET_ZONE) * width + (x - QUIET_ZONE)] == Color::Dark
+    };
+
+    let size = width + 2 * QUIET_ZONE;
+    let lines = (0..size)
+        .step_by(2)
+        .map(|y| {
+            let line = (0..size)
+                .map(|x| match (is_dark(x, y), is_dark(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect::<String>();
+            // dark on light whatever the terminal's theme is, scanners expect that.
+            // forced, with colors off the blocks would take the theme's colors
+            style(line)
+                .black()
+                .on_white()
+                .force_styling(true)
+                .to_string()
+        })
+        .collect::<Vec<String>>();
+    Some(lines.join("\n"))
+}
+
+/// Prints `link` as a QR code sized to the terminal, or says why it can't.
+pub fn print_qr(link: &str) {
+    let term_width = match Term::stdout().size_checked() {
+        Some((_, width)) => width as usize,
+        None => 80,
+    };
+    match render_qr(link, term_width) {
+        Some(qr) => println!("{}", qr),
+        None => eprintln!(
+            "The terminal is too narrow for a QR code of {}, widen it and try again",
+            link
+        ),
+    }
+}
+
+pub async fn file_qr(
+    search: &str,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file =
+        match resolve_shc_file(search, "Which file's QR code do you want?", api_client).await? {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+    let link = file_share_link(&file, api_client.keystore_path()).unwrap_or_else(|link| {
+        warn_missing_key();
+        link
+    });
+    print_qr(&link);
+    println!("{}", link);
+    Ok(())
+}
@@ -1,9 +1,10 @@
-use dialoguer::Confirm;
+use futures::future::join_all;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::tui::{confirm_action, shc_file_multi_input};
+use crate::utils::batch_result;
 
 pub async fn remove_file(
     search: &str,
@@ -28,13 +29,26 @@ pub async fn remove_file(
         return Ok(());
     };
 
-    let selection = shc_file_input(&res.results, "Which file do you want to remove?");
+    let selections = shc_file_multi_input(
+        &res.results,
+        "Which files do you want to remove?",
+        api_client,
+    )?;
+    if selections.is_empty() {
+        println!("No files selected.");
+        return Ok(());
+    }
 
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure?")
-        .default(false)
-        .interact()
-        .unwrap();
+    let files = selections
+        .iter()
+        .map(|&selection| &res.results[selection])
+        .collect::<Vec<_>>();
+    println!("Remove {} file(s):", files.len());
+    for file in &files {
+        println!("  {}", file.name);
+    }
+
+    let confirm = confirm_action(api_client.preferences())?;
 
     if !confirm {
         println!("Aborted");
@@ -48,14 +62,27 @@ pub async fn remove_file(
                 .unwrap()
                 .tick_chars("/|\\- "),
         );
-        pb.set_message("Removing file...");
-        let file_id = res.results[selection].id.clone();
-        let res = api_client.remove_file(file_id.as_str()).await;
+        pb.set_message(format!("Removing {} file(s)...", files.len()));
+        let api_client = &*api_client;
+        let results = join_all(
+            files
+                .iter()
+                .map(|file| async move { (file, api_client.remove_file(&file.id).await) }),
+        )
+        .await;
         pb.finish_and_clear();
-        match res {
-            Ok(_) => println!("Done"),
-            Err(e) => println!("Error: {}", e),
+
+        let mut failed = 0;
+        for (file, res) in results {
+            match res {
+                Ok(_) => println!("Removed {}", file.name),
+                Err(e) => {
+                    failed += 1;
+                    println!("Error: {}: {}", file.name, e)
+                }
+            }
         }
+        batch_result(failed, files.len(), "removals")?;
     }
     Ok(())
 }
@@ -1,14 +1,118 @@
-use dialoguer::{Confirm, Editor};
+use dialoguer::Input;
+use futures::future::join_all;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::models::ShcFile;
+use crate::tui::{confirm_action, resolve_shc_file, shc_file_multi_input};
+use crate::utils::batch_result;
 
-pub async fn rename_file(
-    search: &str,
-    api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
+const MAX_FILENAME_LENGTH: usize = 255;
+const FORBIDDEN_FILENAME_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Trims `name` and checks it works as a filename on every platform it may be downloaded to.
+pub fn validate_filename(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("File name cannot be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("\"{}\" is not a valid file name", name));
+    }
+    if name.chars().count() > MAX_FILENAME_LENGTH {
+        return Err(format!(
+            "File name cannot be longer than {} characters",
+            MAX_FILENAME_LENGTH
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| FORBIDDEN_FILENAME_CHARS.contains(c) || c.is_control())
+    {
+        return Err(format!("File name cannot contain {:?}", c));
+    }
+    Ok(name.to_string())
+}
+
+fn file_extension(file: &ShcFile) -> Option<&str> {
+    let extension = file.extension.trim_start_matches('.');
+    if extension.is_empty() {
+        None
+    } else {
+        Some(extension)
+    }
+}
+
+/// Appends the extension of `file` to `name` unless it already ends with it.
+fn keep_extension(name: String, file: &ShcFile) -> String {
+    match file_extension(file) {
+        Some(extension)
+            if !name
+                .to_lowercase()
+                .ends_with(&format!(".{}", extension.to_lowercase())) =>
+        {
+            format!("{}.{}", name, extension)
+        }
+        _ => name,
+    }
+}
+
+/// Validates `name` as the new name of `file`, with the extension of `file` appended
+/// when `keep_ext` is set. That can make it too long, so it's checked again after.
+fn new_filename(name: &str, file: &ShcFile, keep_ext: bool) -> Result<String, String> {
+    let name = validate_filename(name)?;
+    if keep_ext {
+        validate_filename(&keep_extension(name, file))
+    } else {
+        Ok(name)
+    }
+}
+
+fn name_without_extension(file: &ShcFile) -> &str {
+    file_extension(file)
+        .and_then(|extension| file.name.strip_suffix(&format!(".{}", extension)))
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(&file.name)
+}
+
+/// Asks for the new name of `file`, `None` when it's left unchanged.
+fn new_filename_input(
+    file: &ShcFile,
+    keep_ext: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let initial = if keep_ext {
+        name_without_extension(file)
+    } else {
+        &file.name
+    };
+    let prompt = match file_extension(file) {
+        Some(extension) if keep_ext => {
+            format!("New name for {} (.{} is kept)", file.name, extension)
+        }
+        _ => format!("New name for {}", file.name),
+    };
+
+    let input = Input::<String>::new()
+        .with_prompt(prompt)
+        .with_initial_text(initial)
+        .validate_with(|input: &String| new_filename(input, file, keep_ext).map(|_| ()))
+        .interact_text()?;
+
+    let new_filename = new_filename(&input, file, keep_ext)?;
+    if new_filename == file.name {
+        Ok(None)
+    } else {
+        Ok(Some(new_filename))
+    }
+}
+
+/// Drops renames whose new name is already taken by another file of the user, or by an
+/// earlier rename in the same batch.
+async fn check_unique_names(
+    renames: Vec<(ShcFile, String)>,
+    api_client: &api_client::ApiClient,
+) -> Vec<(ShcFile, String)> {
     let pb = ProgressBar::new_spinner();
 
     pb.enable_steady_tick(Duration::from_millis(200));
@@ -17,54 +121,150 @@ pub async fn rename_file(
             .unwrap()
             .tick_chars("/|\\- "),
     );
-    pb.set_message("Fetching files...");
+    pb.set_message("Checking names...");
+    let results = join_all(renames.into_iter().map(|(file, new_filename)| async move {
+        let res = api_client.list_files(&new_filename).await;
+        (file, new_filename, res)
+    }))
+    .await;
+    pb.finish_and_clear();
+
+    let mut unique: Vec<(ShcFile, String)> = vec![];
+    for (file, new_filename, res) in results {
+        let taken = match res {
+            Ok(res) => res
+                .results
+                .iter()
+                .any(|other| other.id != file.id && other.name == new_filename),
+            Err(e) => {
+                println!("Error: {}: {}", file.name, e);
+                continue;
+            }
+        };
+        if taken || unique.iter().any(|(_, name)| *name == new_filename) {
+            println!(
+                "A file named \"{}\" already exists, skipping {}",
+                new_filename, file.name
+            );
+            continue;
+        }
+        unique.push((file, new_filename));
+    }
+    unique
+}
+
+pub async fn rename_file(
+    search: &str,
+    new_name: Option<&str>,
+    keep_ext: bool,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut renames = vec![];
 
-    let res = api_client.list_files(search).await?;
+    if let Some(new_name) = new_name {
+        validate_filename(new_name)?;
+        let file = match resolve_shc_file(search, "Which file do you want to rename?", api_client)
+            .await?
+        {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let new_filename = new_filename(new_name, &file, keep_ext)?;
+        if new_filename == file.name {
+            println!("\"{}\" already has that name", file.name);
+            return Ok(());
+        }
+        renames.push((file, new_filename));
+    } else {
+        let pb = ProgressBar::new_spinner();
 
-    pb.finish_and_clear();
+        pb.enable_steady_tick(Duration::from_millis(200));
+        pb.set_style(
+            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+                .unwrap()
+                .tick_chars("/|\\- "),
+        );
+        pb.set_message("Fetching files...");
 
-    if res.results.is_empty() {
-        println!("No files found.");
+        let res = api_client.list_files(search).await?;
+
+        pb.finish_and_clear();
+
+        if res.results.is_empty() {
+            println!("No files found.");
+            return Ok(());
+        }
+
+        let selections = shc_file_multi_input(
+            &res.results,
+            "Which files do you want to rename?",
+            api_client,
+        )?;
+        if selections.is_empty() {
+            println!("No files selected.");
+            return Ok(());
+        }
+
+        for (i, file) in res.results.into_iter().enumerate() {
+            if !selections.contains(&i) {
+                continue;
+            }
+            match new_filename_input(&file, keep_ext)? {
+                Some(new_filename) => renames.push((file, new_filename)),
+                None => println!("Name unchanged, skipping {}", file.name),
+            }
+        }
+    }
+
+    let renames = check_unique_names(renames, api_client).await;
+    if renames.is_empty() {
         return Ok(());
     }
 
-    let selection = shc_file_input(&res.results, "Which file do you want to rename?");
+    println!("Rename {} file(s):", renames.len());
+    for (file, new_filename) in &renames {
+        println!("  {} -> {}", file.name, new_filename);
+    }
 
-    if let Some(new_filename) = Editor::new().edit("new filename").unwrap() {
-        let confirm = Confirm::new()
-            .with_prompt("Are you sure?")
-            .default(false)
-            .interact()
-            .unwrap();
+    let confirm = confirm_action(api_client.preferences())?;
 
-        if !confirm {
-            println!("Aborted");
-            return Ok(());
-        } else {
-            let file_id = res.results[selection].id.clone();
-            let pb = ProgressBar::new_spinner();
-
-            pb.enable_steady_tick(Duration::from_millis(200));
-            pb.set_style(
-                ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                    .unwrap()
-                    .tick_chars("/|\\- "),
-            );
-            pb.set_message("Renaming file...");
-            let res = api_client.rename_file(file_id.as_str(), new_filename.as_str());
-            pb.finish_and_clear();
-            match res.await {
+    if !confirm {
+        println!("Aborted");
+        return Ok(());
+    } else {
+        let pb = ProgressBar::new_spinner();
+
+        pb.enable_steady_tick(Duration::from_millis(200));
+        pb.set_style(
+            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+                .unwrap()
+                .tick_chars("/|\\- "),
+        );
+        pb.set_message("Renaming files...");
+        let api_client = &*api_client;
+        let results = join_all(renames.iter().map(|(file, new_filename)| async move {
+            (
+                file,
+                new_filename,
+                api_client.rename_file(&file.id, new_filename).await,
+            )
+        }))
+        .await;
+        pb.finish_and_clear();
+
+        let mut failed = 0;
+        for (file, new_filename, res) in results {
+            match res {
                 Ok(_) => {
-                    println!("File renamed successfully");
+                    println!("Renamed {} to {}", file.name, new_filename);
                 }
                 Err(e) => {
-                    println!("Error: {}", e);
+                    failed += 1;
+                    println!("Error: {}: {}", file.name, e);
                 }
             }
         }
-    } else {
-        // TODO: Handle empty filename correctly
-        println!("ShcFile name cannot be empty");
+        batch_result(failed, renames.len(), "renames")?;
     }
 
     Ok(())
@@ -0,0 +1,395 @@
+use chrono::{DateTime, Local, Utc};
+use console::{style, Term};
+use dialoguer::{theme, Password, Select};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::Error;
+use std::time::Duration;
+
+use crate::api_client;
+use crate::consts::SHC_SHARE_LINK_BASE_URL;
+use crate::models::ShareLink;
+use crate::tui::{confirm_action, resolve_shc_file};
+use crate::utils::batch_result;
+
+/// Lets the user pick one of `rows`, or fails cleanly when there's no terminal to ask on.
+fn select_row(prompt: &str, rows: &[String]) -> Result<usize, Box<dyn std::error::Error>> {
+    if !Term::stderr().is_term() {
+        return Err(Error::other(format!(
+            "Cannot ask \"{}\" without a terminal, pass it as an argument instead",
+            prompt
+        ))
+        .into());
+    }
+    Ok(Select::with_theme(&theme::ColorfulTheme::default())
+        .max_length(20)
+        .with_prompt(prompt)
+        .default(0)
+        .items(rows)
+        .interact()?)
+}
+
+fn share_link_url(link: &ShareLink) -> String {
+    format!("{}/{}", SHC_SHARE_LINK_BASE_URL, link.id)
+}
+
+fn share_link_expiry(link: &ShareLink) -> String {
+    let expires_at = match &link.expires_at {
+        Some(expires_at) => expires_at,
+        None => return "never expires".to_string(),
+    };
+
+    match DateTime::parse_from_rfc3339(expires_at) {
+        Ok(expires_at) if expires_at < Utc::now() => "expired".to_string(),
+        Ok(expires_at) => format!(
+            "expires {}",
+            DateTime::<Local>::from(expires_at).format("%Y-%m-%d %H:%M")
+        ),
+        Err(_) => format!("expires {}", expires_at),
+    }
+}
+
+fn share_link_downloads(link: &ShareLink) -> String {
+    match link.max_downloads {
+        Some(max_downloads) => format!("{}/{} downloads", link.download_count, max_downloads),
+        None => format!("{} downloads", link.download_count),
+    }
+}
+
+fn share_link_row(link: &ShareLink) -> String {
+    format!(
+        "{}  {}  {}  {}{}",
+        share_link_url(link),
+        link.file_name,
+        share_link_expiry(link),
+        share_link_downloads(link),
+        if link.password_protected {
+            "  password"
+        } else {
+            ""
+        }
+    )
+}
+
+pub async fn create_share_link(
+    target: &str,
+    expires_in: Option<Duration>,
+    max_downloads: Option<u64>,
+    with_password: bool,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file =
+        match resolve_shc_file(target, "Which file do you want to share?", api_client).await? {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+    let password = if with_password {
+        Some(
+            Password::with_theme(&theme::ColorfulTheme::default())
+                .with_prompt("Link password")
+                .with_confirmation("Repeat password", "Passwords don't match")
+                .interact()?,
+        )
+    } else {
+        None
+    };
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Creating link...");
+    let res = api_client
+        .create_share_link(
+            &file.id,
+            expires_in.map(|expires_in| expires_in.as_secs()),
+            max_downloads,
+            password.as_deref(),
+        )
+        .await;
+    pb.finish_and_clear();
+
+    let link = res?;
+    println!(
+        "Share link for {}: {}\n{}, {}{}",
+        style(&link.file_name).cyan(),
+        style(share_link_url(&link)).underlined().bright().blue(),
+        share_link_expiry(&link),
+        match link.max_downloads {
+            Some(max_downloads) => format!("at most {} downloads", max_downloads),
+            None => "unlimited downloads".to_string(),
+        },
+        if link.password_protected {
+            ", password protected"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+pub async fn list_share_links(
+    target: Option<&str>,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file_id = match target {
+        Some(target) => {
+            match resolve_shc_file(target, "Which file's links do you want to see?", api_client)
+                .await?
+            {
+                Some(file) => Some(file.id),
+                None => return Ok(()),
+            }
+        }
+        None => None,
+    };
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Fetching links...");
+    let res = api_client.list_share_links(file_id.as_deref()).await?;
+    pb.finish_and_clear();
+
+    if res.results.is_empty() {
+        println!("No share links found.");
+        return Ok(());
+    }
+
+    for link in &res.results {
+        println!("{}", share_link_row(link));
+    }
+    Ok(())
+}
+
+pub async fn revoke_share_link(
+    link_id: Option<&str>,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let link_id = match link_id {
+        // accept full links as well as bare ids
+        Some(link_id) => link_id.rsplit('/').next().unwrap_or(link_id).to_string(),
+        None => {
+            let pb = ProgressBar::new_spinner();
+
+            pb.enable_steady_tick(Duration::from_millis(200));
+            pb.set_style(
+                ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+                    .unwrap()
+                    .tick_chars("/|\\- "),
+            );
+            pb.set_message("Fetching links...");
+            let res = api_client.list_share_links(None).await?;
+            pb.finish_and_clear();
+
+            if res.results.is_empty() {
+                println!("No share links found.");
+                return Ok(());
+            }
+
+            let rows = res
+                .results
+                .iter()
+                .map(share_link_row)
+                .collect::<Vec<String>>();
+            let selection = select_row("Which link do you want to revoke?", &rows)?;
+            res.results[selection].id.clone()
+        }
+    };
+
+    if !confirm_action(api_client.preferences())? {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Revoking link...");
+    let res = api_client.revoke_share_link(&link_id).await;
+    pb.finish_and_clear();
+
+    res?;
+    println!("Link revoked");
+    Ok(())
+}
+
+pub async fn share_with_users(
+    target: &str,
+    emails: &[String],
+    role: &str,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file =
+        match resolve_shc_file(target, "Which file do you want to share?", api_client).await? {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Sharing file...");
+    let mut results = vec![];
+    for email in emails {
+        results.push((
+            email,
+            api_client.create_file_grant(&file.id, email, role).await,
+        ));
+    }
+    pb.finish_and_clear();
+
+    let mut failed = 0;
+    for (email, res) in results {
+        match res {
+            Ok(grant) => println!(
+                "Shared {} with {} as {}",
+                style(&grant.file_name).cyan(),
+                style(&grant.email).green(),
+                grant.role
+            ),
+            Err(e) => {
+                failed += 1;
+                println!("Error: {}: {}", email, e)
+            }
+        }
+    }
+    batch_result(failed, emails.len(), "shares")?;
+    Ok(())
+}
+
+pub async fn list_file_access(
+    target: &str,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = match resolve_shc_file(
+        target,
+        "Which file's access do you want to see?",
+        api_client,
+    )
+    .await?
+    {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Fetching access...");
+    let res = api_client.list_file_grants(&file.id).await?;
+    pb.finish_and_clear();
+
+    let visibility = if file.is_public {
+        "anyone with the link"
+    } else {
+        "only you"
+    };
+    println!("{}: {}", style(&file.name).cyan(), visibility);
+    for grant in &res.results {
+        println!("  {}  {}", style(&grant.email).green(), grant.role);
+    }
+    Ok(())
+}
+
+pub async fn revoke_file_access(
+    target: &str,
+    email: Option<&str>,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = match resolve_shc_file(
+        target,
+        "Which file do you want to revoke access to?",
+        api_client,
+    )
+    .await?
+    {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Fetching access...");
+    let mut res = api_client.list_file_grants(&file.id).await?;
+    pb.finish_and_clear();
+
+    if res.results.is_empty() {
+        println!("{} isn't shared with anyone.", file.name);
+        return Ok(());
+    }
+
+    let selection = match email {
+        Some(email) => match res
+            .results
+            .iter()
+            .position(|grant| grant.email.eq_ignore_ascii_case(email))
+        {
+            Some(position) => position,
+            None => {
+                println!("{} isn't shared with {}.", file.name, email);
+                return Ok(());
+            }
+        },
+        None => {
+            let rows = res
+                .results
+                .iter()
+                .map(|grant| format!("{}  {}", grant.email, grant.role))
+                .collect::<Vec<String>>();
+            select_row("Whose access do you want to revoke?", &rows)?
+        }
+    };
+    let grant = res.results.swap_remove(selection);
+
+    if !confirm_action(api_client.preferences())? {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Revoking access...");
+    let res = api_client.revoke_file_grant(&grant.id).await;
+    pb.finish_and_clear();
+
+    res?;
+    println!("{} no longer has access to {}", grant.email, file.name);
+    Ok(())
+}
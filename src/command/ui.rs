@@ -0,0 +1,456 @@
+use chrono::DateTime;
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::PathBuf;
+
+use crate::api_client::ApiClient;
+use crate::clipboard::copy_to_clipboard;
+use crate::command::add::{upload_file, UploadOptions};
+use crate::command::get::download_shc_file;
+use crate::command::link::file_share_link;
+use crate::command::rename::validate_filename;
+use crate::date::DateFormat;
+use crate::models::ShcFile;
+use crate::size::SizeFormat;
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortKey {
+    Name,
+    Size,
+    Updated,
+    Visibility,
+}
+
+impl SortKey {
+    fn next(self) -> SortKey {
+        match self {
+            SortKey::Name => SortKey::Size,
+            SortKey::Size => SortKey::Updated,
+            SortKey::Updated => SortKey::Visibility,
+            SortKey::Visibility => SortKey::Name,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Updated => "updated",
+            SortKey::Visibility => "visibility",
+        }
+    }
+}
+
+enum Mode {
+    Normal,
+    Rename(String),
+    Upload(String),
+    ConfirmRemove,
+}
+
+struct App {
+    files: Vec<ShcFile>,
+    table_state: TableState,
+    sort_key: SortKey,
+    reverse: bool,
+    mode: Mode,
+    status: String,
+    size_format: SizeFormat,
+    date_format: DateFormat,
+    keystore_path: PathBuf,
+}
+
+impl App {
+    fn selected(&self) -> Option<&ShcFile> {
+        self.table_state
+            .selected()
+            .and_then(|selected| self.files.get(selected))
+    }
+
+    fn sort(&mut self) {
+        let selected_id = self.selected().map(|file| file.id.clone());
+
+        match self.sort_key {
+            SortKey::Name => self.files.sort_by_key(|file| file.name.to_lowercase()),
+            SortKey::Size => self.files.sort_by_key(|file| file.size),
+            SortKey::Updated => self.files.sort_by_key(|file| {
+                DateTime::parse_from_rfc3339(&file.updated_at)
+                    .map(|updated_at| updated_at.timestamp())
+                    .unwrap_or_default()
+            }),
+            SortKey::Visibility => self.files.sort_by_key(|file| !file.is_public),
+        }
+        if self.reverse {
+            self.files.reverse();
+        }
+
+        let selected = selected_id
+            .and_then(|id| self.files.iter().position(|file| file.id == id))
+            .or(if self.files.is_empty() { None } else { Some(0) });
+        self.table_state.select(selected);
+    }
+
+    async fn refresh(&mut self, api_client: &ApiClient) {
+        match api_client.list_files("").await {
+            Ok(res) => {
+                self.files = res.results;
+                self.sort();
+            }
+            Err(e) => self.status = format!("Error: {}", e),
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [main_area, status_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(2)]).areas(frame.area());
+    let [table_area, detail_area] =
+        Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .areas(main_area);
+
+    let header = Row::new(["Name", "Size", "Updated", "Visibility"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = app.files.iter().map(|file| {
+        Row::new([
+            Cell::from(file.name.clone()),
+            Cell::from(app.size_format.format(file.size)),
+            Cell::from(app.date_format.format(&file.updated_at)),
+            Cell::from(if file.is_public { "Public" } else { "Private" }),
+        ])
+    });
+    let title = format!(
+        " {} files, sorted by {}{} ",
+        app.files.len(),
+        app.sort_key.label(),
+        if app.reverse { " (reversed)" } else { "" }
+    );
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(11),
+            Constraint::Length(app.date_format.width() as u16),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .highlight_symbol("❯ ");
+    frame.render_stateful_widget(table, table_area, &mut app.table_state);
+
+    let details = match app.selected() {
+        Some(file) => {
+            let updated_at = app.date_format.format_long(&file.updated_at);
+            let field = |label: &str, value: String, color: Color| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{}: ", label),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(value, Style::default().fg(color)),
+                ])
+            };
+            vec![
+                field("File Name", file.name.clone(), Color::Cyan),
+                field("Upload Status", file.upload_status.clone(), Color::Yellow),
+                field("Updated At", updated_at, Color::Green),
+                field("Size", app.size_format.format(file.size), Color::Magenta),
+                field(
+                    "Visibility",
+                    if file.is_public { "Public" } else { "Private" }.to_string(),
+                    Color::Blue,
+                ),
+                field(
+                    "Shareable Link",
+                    file_share_link(file, &app.keystore_path).unwrap_or_else(|link| link),
+                    Color::LightBlue,
+                ),
+            ]
+        }
+        None => vec![Line::from("No files")],
+    };
+    frame.render_widget(
+        Paragraph::new(details)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(" Details ")),
+        detail_area,
+    );
+
+    let prompt = match &app.mode {
+        Mode::Normal => app.status.clone(),
+        Mode::Rename(name) => format!("New name: {}▏", name),
+        Mode::Upload(path) => format!("File to upload: {}▏", path),
+        Mode::ConfirmRemove => format!(
+            "Remove {}? (y/n)",
+            app.selected()
+                .map(|file| file.name.as_str())
+                .unwrap_or_default()
+        ),
+    };
+    let help = "↑↓ move  d download  r rename  x remove  v visibility  c copy link  u upload  s sort  S reverse  g refresh  q quit";
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(prompt),
+            Line::styled(help, Style::default().fg(Color::DarkGray)),
+        ]),
+        status_area,
+    );
+}
+
+/// Full-screen file browser, every action refreshes the file list afterwards.
+pub async fn run_ui(api_client: &mut ApiClient) -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = App {
+        files: vec![],
+        table_state: TableState::default(),
+        sort_key: SortKey::Updated,
+        reverse: true,
+        mode: Mode::Normal,
+        status: "Fetching files...".to_string(),
+        size_format: api_client.preferences().size_format(),
+        date_format: api_client.preferences().date_format(),
+        keystore_path: api_client.keystore_path().to_path_buf(),
+    };
+
+    let mut terminal = ratatui::init();
+    terminal.draw(|frame| draw(frame, &mut app))?;
+    app.refresh(api_client).await;
+    app.status.clear();
+
+    let res = run_app(&mut terminal, &mut app, api_client).await;
+    ratatui::restore();
+    res
+}
+
+async fn run_app(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    api_client: &mut ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        // nothing works without a session, leave instead of failing on every key
+        if api_client.is_logged_out() {
+            return Err(std::io::Error::other("Logged out, please login again").into());
+        }
+        terminal.draw(|frame| draw(frame, app))?;
+
+        let key = match tokio::task::spawn_blocking(event::read).await?? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(());
+        }
+        // shortcuts and typed names are plain keys, Ctrl-V shouldn't toggle visibility
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            continue;
+        }
+
+        match &mut app.mode {
+            Mode::Rename(name) | Mode::Upload(name) => match key.code {
+                KeyCode::Char(c) => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Esc => app.mode = Mode::Normal,
+                KeyCode::Enter => {
+                    let mode = std::mem::replace(&mut app.mode, Mode::Normal);
+                    match mode {
+                        Mode::Rename(name) => rename(app, name, api_client).await,
+                        Mode::Upload(path) => {
+                            upload(terminal, app, PathBuf::from(path.trim()), api_client).await?
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            },
+            Mode::ConfirmRemove => {
+                app.mode = Mode::Normal;
+                if key.code == KeyCode::Char('y') {
+                    remove(app, api_client).await;
+                }
+            }
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => app.table_state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => app.table_state.select_previous(),
+                KeyCode::Char('s') => {
+                    app.sort_key = app.sort_key.next();
+                    app.sort();
+                }
+                KeyCode::Char('S') => {
+                    app.reverse = !app.reverse;
+                    app.sort();
+                }
+                KeyCode::Char('g') => {
+                    app.status = "Refreshing...".to_string();
+                    terminal.draw(|frame| draw(frame, app))?;
+                    app.refresh(api_client).await;
+                    app.status.clear();
+                }
+                KeyCode::Char('u') => app.mode = Mode::Upload(String::new()),
+                _ if app.selected().is_none() => {}
+                KeyCode::Char('d') => {
+                    app.status = "Downloading...".to_string();
+                    terminal.draw(|frame| draw(frame, app))?;
+                    download(app, api_client).await;
+                }
+                KeyCode::Char('r') => {
+                    let name = app
+                        .selected()
+                        .map(|file| file.name.clone())
+                        .unwrap_or_default();
+                    app.mode = Mode::Rename(name);
+                }
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    if api_client.preferences().confirm() {
+                        app.mode = Mode::ConfirmRemove;
+                    } else {
+                        remove(app, api_client).await;
+                    }
+                }
+                KeyCode::Char('v') => {
+                    let file_id = app
+                        .selected()
+                        .map(|file| file.id.clone())
+                        .unwrap_or_default();
+                    app.status = match api_client.toggle_file_visibility(&file_id).await {
+                        Ok(file) => {
+                            let visibility = if file.is_public { "Public" } else { "Private" };
+                            format!("Visibility of \"{}\" changed to {}", file.name, visibility)
+                        }
+                        Err(e) => format!("Error: {}", e),
+                    };
+                    app.refresh(api_client).await;
+                }
+                KeyCode::Char('c') => {
+                    let link = match app.selected() {
+                        Some(file) => file_share_link(file, api_client.keystore_path()),
+                        None => continue,
+                    };
+                    app.status = match link {
+                        Ok(link) => match copy_to_clipboard(&link) {
+                            Ok(_) => format!("Copied {}", link),
+                            Err(e) => format!("Error: {}", e),
+                        },
+                        Err(_) => "Error: no key is stored for this encrypted file".to_string(),
+                    };
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+async fn download(app: &mut App, api_client: &ApiClient) {
+    let file = match app.selected() {
+        Some(file) => file,
+        None => return,
+    };
+
+    let download_dir = api_client.preferences().download_dir();
+    let client = reqwest::Client::new();
+    // progress bars would draw over the ui
+    let multi_progress = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+    let res = match std::fs::create_dir_all(&download_dir) {
+        Ok(_) => {
+            download_shc_file(
+                file,
+                &download_dir,
+                &client,
+                &multi_progress,
+                None,
+                api_client,
+            )
+            .await
+        }
+        Err(e) => Err(e.into()),
+    };
+    app.status = match res {
+        Ok(file_path) => format!("Downloaded {}", file_path.display()),
+        Err(e) => format!("Error: {}", e),
+    };
+    app.refresh(api_client).await;
+}
+
+async fn rename(app: &mut App, new_name: String, api_client: &ApiClient) {
+    let file_id = match app.selected() {
+        Some(file) => file.id.clone(),
+        None => return,
+    };
+
+    let new_name = match validate_filename(&new_name) {
+        Ok(new_name) => new_name,
+        Err(e) => {
+            app.status = format!("Error: {}", e);
+            return;
+        }
+    };
+    if app
+        .files
+        .iter()
+        .any(|file| file.id != file_id && file.name == new_name)
+    {
+        app.status = format!("Error: A file named \"{}\" already exists", new_name);
+        return;
+    }
+
+    app.status = match api_client.rename_file(&file_id, &new_name).await {
+        Ok(_) => format!("Renamed to {}", new_name),
+        Err(e) => format!("Error: {}", e),
+    };
+    app.refresh(api_client).await;
+}
+
+async fn remove(app: &mut App, api_client: &ApiClient) {
+    let (file_id, file_name) = match app.selected() {
+        Some(file) => (file.id.clone(), file.name.clone()),
+        None => return,
+    };
+
+    app.status = match api_client.remove_file(&file_id).await {
+        Ok(_) => format!("Removed {}", file_name),
+        Err(e) => format!("Error: {}", e),
+    };
+    app.refresh(api_client).await;
+}
+
+/// Leaves the ui while uploading so the usual progress output is visible.
+async fn upload(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    file_path: PathBuf,
+    api_client: &mut ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !file_path.exists() {
+        app.status = format!("File not found: {}", file_path.display());
+        return Ok(());
+    }
+
+    ratatui::restore();
+    let visibility = api_client.preferences().default_visibility();
+    let res = upload_file(&file_path, UploadOptions::new(visibility), api_client).await;
+    if let Err(e) = &res {
+        println!("Error: {}", e);
+    }
+    println!("\nPress Enter to return to shc ui");
+    std::io::stdin().read_line(&mut String::new())?;
+    *terminal = ratatui::init();
+
+    app.status = match res {
+        Ok(Some(_)) => format!("Uploaded {}", file_path.display()),
+        Ok(None) => format!("{} was not uploaded", file_path.display()),
+        Err(e) => format!("Error: {}", e),
+    };
+    app.refresh(api_client).await;
+    Ok(())
+}
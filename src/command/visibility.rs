@@ -1,8 +1,11 @@
-use dialoguer::Confirm;
+use futures::future::join_all;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
 
-use crate::{api_client, tui::shc_file_input};
+use crate::api_client;
+use crate::preferences::Visibility;
+use crate::tui::{confirm_action, resolve_shc_file, shc_file_multi_input};
+use crate::utils::batch_result;
 
 pub async fn toggle_file_visibility(
     search: &str,
@@ -25,13 +28,31 @@ pub async fn toggle_file_visibility(
         return Ok(());
     }
 
-    let selection = shc_file_input(&res.results, "Which file do you want to change visibility?");
+    let selections = shc_file_multi_input(
+        &res.results,
+        "Which files do you want to change visibility?",
+        api_client,
+    )?;
+    if selections.is_empty() {
+        println!("No files selected.");
+        return Ok(());
+    }
 
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure?")
-        .default(false)
-        .interact()
-        .unwrap();
+    let files = selections
+        .iter()
+        .map(|&selection| &res.results[selection])
+        .collect::<Vec<_>>();
+    println!("Toggle visibility of {} file(s):", files.len());
+    for file in &files {
+        let (from, to) = if file.is_public {
+            ("Public", "Private")
+        } else {
+            ("Private", "Public")
+        };
+        println!("  {} ({} -> {})", file.name, from, to);
+    }
+
+    let confirm = confirm_action(api_client.preferences())?;
 
     if !confirm {
         println!("Aborted");
@@ -46,18 +67,100 @@ pub async fn toggle_file_visibility(
                 .tick_chars("/|\\- "),
         );
         pb.set_message("Toggling visibility...");
-        let file_id = res.results[selection].id.clone();
-        let res = api_client.toggle_file_visibility(file_id.as_str()).await;
+        let api_client = &*api_client;
+        let results =
+            join_all(files.iter().map(|file| async move {
+                (file, api_client.toggle_file_visibility(&file.id).await)
+            }))
+            .await;
         pb.finish_and_clear();
-        match res {
-            Ok(res) => {
-                let visiblity = if res.is_public { "Public" } else { "Private" };
-                println!("Visibility of \"{}\" changed to {}", res.name, visiblity);
+
+        let mut failed = 0;
+        for (file, res) in results {
+            match res {
+                Ok(res) => {
+                    let visiblity = if res.is_public { "Public" } else { "Private" };
+                    println!("Visibility of \"{}\" changed to {}", res.name, visiblity);
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("Error: {}: {}", file.name, e)
+                }
             }
+        }
+        batch_result(failed, files.len(), "visibility changes")?;
+    }
+    Ok(())
+}
+
+pub async fn set_file_visibility(
+    targets: &[String],
+    visibility: Visibility,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let is_public = visibility == Visibility::Public;
+    let visibility = if is_public { "Public" } else { "Private" };
+
+    let mut files = vec![];
+    for target in targets {
+        let prompt = format!("Which file do you mean by \"{}\"?", target);
+        if let Some(file) = resolve_shc_file(target, &prompt, api_client).await? {
+            files.push(file);
+        }
+    }
+
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "Make {} {}:",
+        if files.len() == 1 {
+            "this file"
+        } else {
+            "these files"
+        },
+        visibility
+    );
+    for file in &files {
+        println!("  {}", file.name);
+    }
+
+    if !confirm_action(api_client.preferences())? {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Updating visibility...");
+    let api_client = &*api_client;
+    let results = join_all(files.iter().map(|file| async move {
+        (
+            &file.name,
+            api_client.set_file_visibility(&file.id, is_public).await,
+        )
+    }))
+    .await;
+    pb.finish_and_clear();
+
+    let mut failed = 0;
+    for (name, res) in results {
+        match res {
+            Ok((_, true)) => println!("Visibility of \"{}\" changed to {}", name, visibility),
+            Ok((_, false)) => println!("\"{}\" is already {}", name, visibility),
             Err(e) => {
-                println!("Error: {}", e)
+                failed += 1;
+                println!("Error: \"{}\": {}", name, e)
             }
         }
     }
+    batch_result(failed, files.len(), "visibility changes")?;
     Ok(())
 }
@@ -1,7 +1,14 @@
 pub const SHC_BACKEND_API_BASE_URL: &str = "https://shc.up.railway.app";
+pub const SHC_SHARE_BASE_URL: &str = "https://shc.ajaysharma.dev/share";
+pub const SHC_SHARE_LINK_BASE_URL: &str = "https://shc.ajaysharma.dev/s";
 
 pub const SHC_CLI_FOLDER_NAME: &str = ".shc-cli";
+pub const SHC_XDG_FOLDER_NAME: &str = "shc-cli";
 pub const USER_CONFIG_FILE_NAME: &str = "config.toml";
+pub const SHC_CONFIG_ENV: &str = "SHC_CONFIG";
+pub const CONFIG_VERSION: u32 = 1;
 pub const SHC_IGNORE_FILE_NAME: &str = ".shcignore";
+pub const SHC_SECRETS_FILE_NAME: &str = ".shcsecrets";
+pub const SHC_QUARANTINE_FOLDER_NAME: &str = ".shc-quarantine";
 
-pub const MAX_NAME_WIDTH_LENGTH: usize = 50;
+pub const MIN_NAME_WIDTH: usize = 10;
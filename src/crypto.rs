@@ -0,0 +1,195 @@
+use base64::prelude::*;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::{KeyInit, OsRng};
+use chacha20poly1305::XChaCha20Poly1305;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Start of every encrypted file, followed by the nonce prefix of the stream.
+const MAGIC: &[u8; 8] = b"SHCENC1\0";
+/// XChaCha20's 24 byte nonce minus the 5 bytes the STREAM construction counts with
+const NONCE_PREFIX_LEN: usize = 19;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+
+/// Key of a single encrypted file. It only ever leaves the machine in the
+/// fragment of a share link, which browsers don't send to the server.
+pub struct FileKey([u8; 32]);
+
+impl FileKey {
+    pub fn generate() -> FileKey {
+        FileKey(XChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    pub fn to_base64(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(self.0)
+    }
+
+    pub fn from_base64(key: &str) -> Result<FileKey, String> {
+        BASE64_URL_SAFE_NO_PAD
+            .decode(key.trim())
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .map(FileKey)
+            .ok_or_else(|| "Invalid encryption key".to_string())
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0.into())
+    }
+}
+
+/// Like `read_exact`, but a short read at the end of the file is fine.
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+fn crypto_error(_: chacha20poly1305::aead::Error) -> io::Error {
+    io::Error::other("Decryption failed, the key is wrong or the file is damaged")
+}
+
+/// Encrypts `src` into `dest` in chunks with XChaCha20-Poly1305, so files
+/// of any size never have to fit in memory.
+pub fn encrypt_file(src: &Path, dest: &Path, key: &FileKey) -> io::Result<()> {
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+    OsRng.fill_bytes(&mut nonce_prefix);
+    let mut encryptor = EncryptorBE32::from_aead(key.cipher(), &nonce_prefix.into());
+
+    let mut reader = BufReader::new(File::open(src)?);
+    let mut writer = BufWriter::new(File::create(dest)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&nonce_prefix)?;
+
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut next = vec![0u8; CHUNK_SIZE];
+    let mut len = read_chunk(&mut reader, &mut chunk)?;
+    // the last chunk is sealed differently, so always read one ahead
+    loop {
+        let next_len = read_chunk(&mut reader, &mut next)?;
+        if next_len == 0 {
+            let ciphertext = encryptor
+                .encrypt_last(&chunk[..len])
+                .map_err(crypto_error)?;
+            writer.write_all(&ciphertext)?;
+            break;
+        }
+        let ciphertext = encryptor
+            .encrypt_next(&chunk[..len])
+            .map_err(crypto_error)?;
+        writer.write_all(&ciphertext)?;
+        std::mem::swap(&mut chunk, &mut next);
+        len = next_len;
+    }
+    writer.flush()
+}
+
+pub fn decrypt_file(src: &Path, dest: &Path, key: &FileKey) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let mut header = [0u8; MAGIC.len() + NONCE_PREFIX_LEN];
+    reader.read_exact(&mut header)?;
+    if &header[..MAGIC.len()] != MAGIC {
+        return Err(io::Error::other("File is not encrypted"));
+    }
+    let nonce_prefix: [u8; NONCE_PREFIX_LEN] = header[MAGIC.len()..].try_into().unwrap();
+    let mut decryptor = DecryptorBE32::from_aead(key.cipher(), &nonce_prefix.into());
+
+    let mut writer = BufWriter::new(File::create(dest)?);
+    let mut chunk = vec![0u8; CHUNK_SIZE + TAG_LEN];
+    let mut next = vec![0u8; CHUNK_SIZE + TAG_LEN];
+    let mut len = read_chunk(&mut reader, &mut chunk)?;
+    loop {
+        let next_len = read_chunk(&mut reader, &mut next)?;
+        if next_len == 0 {
+            let plaintext = decryptor
+                .decrypt_last(&chunk[..len])
+                .map_err(crypto_error)?;
+            writer.write_all(&plaintext)?;
+            break;
+        }
+        let plaintext = decryptor
+            .decrypt_next(&chunk[..len])
+            .map_err(crypto_error)?;
+        writer.write_all(&plaintext)?;
+        std::mem::swap(&mut chunk, &mut next);
+        len = next_len;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(len: usize) {
+        let dir = tempfile::tempdir().unwrap();
+        let (plain, encrypted, decrypted) = (
+            dir.path().join("plain"),
+            dir.path().join("encrypted"),
+            dir.path().join("decrypted"),
+        );
+        let content = (0..len).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        std::fs::write(&plain, &content).unwrap();
+
+        let key = FileKey::generate();
+        encrypt_file(&plain, &encrypted, &key).unwrap();
+        decrypt_file(&encrypted, &decrypted, &key).unwrap();
+        assert_eq!(std::fs::read(&decrypted).unwrap(), content);
+    }
+
+    #[test]
+    fn round_trips_at_chunk_boundaries() {
+        for len in [
+            0,
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            2 * CHUNK_SIZE,
+            2 * CHUNK_SIZE + 17,
+        ] {
+            round_trip(len);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_key_and_damaged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plain, encrypted, decrypted) = (
+            dir.path().join("plain"),
+            dir.path().join("encrypted"),
+            dir.path().join("decrypted"),
+        );
+        std::fs::write(&plain, vec![7u8; 2 * CHUNK_SIZE + 5]).unwrap();
+        let key = FileKey::generate();
+        encrypt_file(&plain, &encrypted, &key).unwrap();
+
+        assert!(decrypt_file(&encrypted, &decrypted, &FileKey::generate()).is_err());
+
+        let mut damaged = std::fs::read(&encrypted).unwrap();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 1;
+        std::fs::write(&encrypted, &damaged).unwrap();
+        assert!(decrypt_file(&encrypted, &decrypted, &key).is_err());
+
+        // dropping whole chunks off the end must not go unnoticed either
+        damaged.truncate(MAGIC.len() + NONCE_PREFIX_LEN + CHUNK_SIZE + TAG_LEN);
+        std::fs::write(&encrypted, &damaged).unwrap();
+        assert!(decrypt_file(&encrypted, &decrypted, &key).is_err());
+    }
+
+    #[test]
+    fn key_survives_base64() {
+        let key = FileKey::generate();
+        assert_eq!(FileKey::from_base64(&key.to_base64()).unwrap().0, key.0);
+        assert!(FileKey::from_base64("not a key").is_err());
+    }
+}
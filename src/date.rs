@@ -0,0 +1,59 @@
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateFormat {
+    /// 3 days ago
+    Relative,
+    /// 2024-01-31 14:05 in the local timezone
+    Absolute,
+    /// 2024-01-31T14:05:12+01:00 in the local timezone
+    Iso,
+}
+
+impl DateFormat {
+    /// Table column width that fits what `format` returns.
+    pub fn width(&self) -> usize {
+        match self {
+            DateFormat::Relative => 14,
+            DateFormat::Absolute => 16,
+            DateFormat::Iso => 25,
+        }
+    }
+
+    /// Short form for tables, `-` when the backend sent something that isn't RFC 3339.
+    pub fn format(&self, timestamp: &str) -> String {
+        let timestamp = match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(timestamp) => timestamp,
+            Err(_) => return "-".to_string(),
+        };
+        match self {
+            DateFormat::Relative => timeago::Formatter::new().convert_chrono(timestamp, Utc::now()),
+            DateFormat::Absolute => timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            DateFormat::Iso => timestamp
+                .with_timezone(&Local)
+                .to_rfc3339_opts(SecondsFormat::Secs, false),
+        }
+    }
+
+    /// Full form for detail views, falls back to the raw timestamp when it can't be parsed.
+    pub fn format_long(&self, timestamp: &str) -> String {
+        let parsed = match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(parsed) => parsed.with_timezone(&Local),
+            Err(_) => return timestamp.to_string(),
+        };
+        match self {
+            DateFormat::Relative => format!(
+                "{} ({})",
+                parsed.format("%Y-%m-%d %H:%M:%S %:z"),
+                self.format(timestamp)
+            ),
+            DateFormat::Absolute => parsed.format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+            DateFormat::Iso => parsed.to_rfc3339_opts(SecondsFormat::Secs, false),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+use crate::models::ShcFile;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortField {
+    Name,
+    Size,
+    Updated,
+    Downloads,
+}
+
+impl SortField {
+    pub fn parse(sort: &str) -> Option<SortField> {
+        match sort {
+            "name" => Some(SortField::Name),
+            "size" => Some(SortField::Size),
+            "updated" => Some(SortField::Updated),
+            "downloads" => Some(SortField::Downloads),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortField::Name => "name",
+            SortField::Size => "size",
+            SortField::Updated => "updated",
+            SortField::Downloads => "downloads",
+        }
+    }
+}
+
+/// Filters for listing files. They're sent to the backend and applied again
+/// to the results, so they work even where the backend ignores them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileFilters {
+    pub sort: Option<SortField>,
+    pub reverse: bool,
+    /// an extension (`rs`, `.rs`) or a mime type (`image/png`, `image/*`)
+    pub file_type: Option<String>,
+    pub is_public: Option<bool>,
+    pub status: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// hex SHA-256 of the content
+    pub checksum: Option<String>,
+}
+
+impl FileFilters {
+    pub fn is_empty(&self) -> bool {
+        *self == FileFilters::default()
+    }
+
+    pub fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![];
+        if let Some(sort) = self.sort {
+            query.push(("sort", sort.as_str().to_string()));
+            let order = if self.reverse { "desc" } else { "asc" };
+            query.push(("order", order.to_string()));
+        }
+        if let Some(file_type) = &self.file_type {
+            query.push(("type", file_type.clone()));
+        }
+        if let Some(is_public) = self.is_public {
+            query.push(("is_public", is_public.to_string()));
+        }
+        if let Some(status) = &self.status {
+            query.push(("status", status.clone()));
+        }
+        if let Some(since) = self.since {
+            query.push(("since", since.to_rfc3339()));
+        }
+        if let Some(before) = self.before {
+            query.push(("before", before.to_rfc3339()));
+        }
+        if let Some(min_size) = self.min_size {
+            query.push(("min_size", min_size.to_string()));
+        }
+        if let Some(max_size) = self.max_size {
+            query.push(("max_size", max_size.to_string()));
+        }
+        if let Some(checksum) = &self.checksum {
+            query.push(("checksum", checksum.clone()));
+        }
+        query
+    }
+
+    pub fn matches(&self, file: &ShcFile) -> bool {
+        if let Some(file_type) = &self.file_type {
+            let file_type = file_type.to_lowercase();
+            let matches_type = match file_type.split_once('/') {
+                Some((kind, "*")) => file
+                    .mime_type
+                    .to_lowercase()
+                    .starts_with(&format!("{}/", kind)),
+                Some(_) => file.mime_type.eq_ignore_ascii_case(&file_type),
+                None => file
+                    .extension
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(file_type.trim_start_matches('.')),
+            };
+            if !matches_type {
+                return false;
+            }
+        }
+
+        if self
+            .is_public
+            .is_some_and(|is_public| file.is_public != is_public)
+        {
+            return false;
+        }
+        if let Some(status) = &self.status {
+            if !file.upload_status.eq_ignore_ascii_case(status) {
+                return false;
+            }
+        }
+        if let Some(checksum) = &self.checksum {
+            let matches_checksum = file
+                .checksum
+                .as_ref()
+                .is_some_and(|file_checksum| file_checksum.eq_ignore_ascii_case(checksum));
+            if !matches_checksum {
+                return false;
+            }
+        }
+        if self.min_size.is_some_and(|min_size| file.size < min_size)
+            || self.max_size.is_some_and(|max_size| file.size > max_size)
+        {
+            return false;
+        }
+
+        if self.since.is_some() || self.before.is_some() {
+            let updated_at = match DateTime::parse_from_rfc3339(&file.updated_at) {
+                Ok(updated_at) => updated_at.with_timezone(&Utc),
+                Err(_) => return false,
+            };
+            if self.since.is_some_and(|since| updated_at < since)
+                || self.before.is_some_and(|before| updated_at >= before)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Drops the files that don't match and sorts the rest.
+    pub fn apply(&self, files: &mut Vec<ShcFile>) {
+        files.retain(|file| self.matches(file));
+
+        let sort = match self.sort {
+            Some(sort) => sort,
+            None => return,
+        };
+        files.sort_by(|a, b| {
+            let ordering = match sort {
+                SortField::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                SortField::Size => a.size.cmp(&b.size),
+                SortField::Updated => {
+                    match (
+                        DateTime::parse_from_rfc3339(&a.updated_at),
+                        DateTime::parse_from_rfc3339(&b.updated_at),
+                    ) {
+                        (Ok(a), Ok(b)) => a.cmp(&b),
+                        _ => Ordering::Equal,
+                    }
+                }
+                SortField::Downloads => a.download_count.cmp(&b.download_count),
+            };
+            if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+use crate::crypto::FileKey;
+
+/// Keys of the files the user encrypted, by file id, so `shc get` can
+/// decrypt them without the share link. Lives next to the config file.
+pub struct Keystore {
+    path: PathBuf,
+    keys: BTreeMap<String, String>,
+}
+
+impl Keystore {
+    pub fn load(path: &Path) -> Result<Keystore, Box<dyn std::error::Error>> {
+        let keys = if path.exists() {
+            toml::from_str(&fs::read_to_string(path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Keystore {
+            path: path.to_path_buf(),
+            keys,
+        })
+    }
+
+    pub fn get(&self, file_id: &str) -> Option<FileKey> {
+        self.keys
+            .get(file_id)
+            .and_then(|key| FileKey::from_base64(key).ok())
+    }
+
+    pub fn insert(&mut self, file_id: &str, key: &FileKey) {
+        self.keys.insert(file_id.to_string(), key.to_base64());
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let folder = match self.path.parent() {
+            Some(folder) if !folder.as_os_str().is_empty() => folder,
+            _ => Path::new("."),
+        };
+        // NamedTempFile is only readable by the user, which persist keeps
+        let mut file = NamedTempFile::new_in(folder)?;
+        file.write_all(toml::to_string(&self.keys)?.as_bytes())?;
+        file.as_file().sync_all()?;
+        file.persist(&self.path)?;
+        Ok(())
+    }
+}
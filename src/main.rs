@@ -1,52 +1,191 @@
 mod api_client;
 mod cli;
+mod clipboard;
 mod command;
 mod consts;
+mod crypto;
+mod date;
+mod filters;
+mod keystore;
 mod models;
+mod preferences;
+mod rate_limit;
+mod secrets;
+mod size;
+mod snippet;
 mod tui;
 mod user_config;
 mod utils;
 
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::api_client::ApiClient;
+use crate::clipboard::read_clipboard;
+use crate::command::add::UploadOptions;
 use crate::command::auth::{check_for_api_key, login, logout};
+use crate::command::link::copy_link;
+use crate::command::list::ListOutput;
+use crate::command::qr::print_qr;
+use crate::filters::{FileFilters, SortField};
+use crate::models::LineRange;
+use crate::preferences::{ColorChoice, Visibility};
+use crate::snippet::{split_path_range, Selection};
 use crate::user_config::UserConfig;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut user_config = UserConfig::new();
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("{} {}", console::style("Error:").red().bold(), e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let matches = cli::cli().get_matches();
+    let config_path = matches.get_one::<String>("config").map(PathBuf::from);
+    let mut user_config = UserConfig::new(config_path)?;
+
+    match user_config.preferences.color() {
+        ColorChoice::Auto => {}
+        ColorChoice::Always => {
+            console::set_colors_enabled(true);
+            console::set_colors_enabled_stderr(true);
+        }
+        ColorChoice::Never => {
+            console::set_colors_enabled(false);
+            console::set_colors_enabled_stderr(false);
+        }
+    }
 
     match matches.subcommand() {
         Some(("login", _)) => login(&mut user_config).await?,
-        Some(("logout", _)) => logout(&mut user_config),
+        Some(("logout", sub_matches)) => {
+            logout(&mut user_config, sub_matches.get_flag("all-profiles")).await?
+        }
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("get", sub_matches)) => {
+                let key = sub_matches.get_one::<String>("KEY").expect("required");
+                command::config::get_preference(key, &user_config)?;
+            }
+            Some(("set", sub_matches)) => {
+                let key = sub_matches.get_one::<String>("KEY").expect("required");
+                let value = sub_matches.get_one::<String>("VALUE").expect("required");
+                command::config::set_preference(key, value, &mut user_config)?;
+            }
+            Some(("unset", sub_matches)) => {
+                let key = sub_matches.get_one::<String>("KEY").expect("required");
+                command::config::unset_preference(key, &mut user_config)?;
+            }
+            Some(("list", _)) => command::config::list_preferences(&user_config)?,
+            Some(("edit", _)) => command::config::edit_preferences(&mut user_config)?,
+            _ => println!("Command not found."),
+        },
         None => println!("No subcommand was used"),
 
         _ => {
             check_for_api_key(&mut user_config).await?;
             let mut api_client = ApiClient::new(user_config);
+            if let Some(&rate) = matches.get_one::<u64>("limit-rate") {
+                api_client.set_limit_rate(rate);
+            }
             match matches.subcommand() {
-                
                 Some(("add", sub_matches)) => {
-                    let file = sub_matches.get_one::<String>("FILE").expect("required");
-                    let file_path = PathBuf::from(file);
-                    if !file_path.exists() {
-                        println!("File not found");
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::NotFound,
-                            "File not found",
-                        )
-                        .into());
+                    let visibility = if sub_matches.get_flag("public") {
+                        Visibility::Public
+                    } else if sub_matches.get_flag("private") {
+                        Visibility::Private
+                    } else {
+                        api_client.preferences().default_visibility()
+                    };
+
+                    let options = UploadOptions {
+                        allow_secrets: sub_matches.get_flag("allow-secrets"),
+                        force_new: sub_matches.get_flag("force-new"),
+                        encrypt: sub_matches.get_flag("encrypt"),
+                        ..UploadOptions::new(visibility)
+                    };
+
+                    let link = if sub_matches.get_flag("from-clipboard") {
+                        let content = read_clipboard()?;
+                        let file_name = command::new::file_name(None, "clipboard", None)?;
+                        command::new::upload_text(&file_name, &content, options, &mut api_client)
+                            .await?
+                    } else {
+                        let file = sub_matches.get_one::<String>("FILE").expect("required");
+                        // `path:20-45`, unless a file with that exact name exists
+                        let (file, path_range) = match split_path_range(file) {
+                            Some((path, range)) if !Path::new(file).exists() => (path, Some(range)),
+                            _ => (file.as_str(), None),
+                        };
+                        let file_path = PathBuf::from(file);
+                        if !file_path.exists() {
+                            println!("File not found");
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::NotFound,
+                                "File not found",
+                            )
+                            .into());
+                        }
+                        let lines = sub_matches.get_one::<LineRange>("lines").copied();
+                        let grep = sub_matches.get_one::<Regex>("grep");
+                        if path_range.is_some() && (lines.is_some() || grep.is_some()) {
+                            return Err(std::io::Error::other(
+                                "Use only one of FILE:RANGE, --lines or --grep",
+                            )
+                            .into());
+                        }
+                        let selection = match (path_range.or(lines), grep) {
+                            (Some(range), _) => Some(Selection::Lines(range)),
+                            (None, Some(pattern)) => Some(Selection::Grep(
+                                pattern.clone(),
+                                *sub_matches
+                                    .get_one::<usize>("context")
+                                    .expect("has default"),
+                            )),
+                            (None, None) => None,
+                        };
+                        match selection {
+                            Some(selection) => {
+                                command::add::upload_snippet(
+                                    &file_path,
+                                    file,
+                                    &selection,
+                                    options,
+                                    &mut api_client,
+                                )
+                                .await?
+                            }
+                            None => {
+                                command::add::upload_file(&file_path, options, &mut api_client)
+                                    .await?
+                            }
+                        }
+                    };
+                    if let Some(link) = link {
+                        if sub_matches.get_flag("qr") {
+                            print_qr(&link);
+                        }
+                        if sub_matches.get_flag("copy") {
+                            copy_link(&link);
+                        }
                     }
-                    command::add::upload_file(&file_path, &mut api_client).await?;
                 }
 
                 Some(("rename", sub_matches)) => {
                     let default: String = "".to_string();
                     //TODO: todo rename search to filter
                     let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::rename::rename_file(search, &mut api_client).await?;
+                    let new_name = sub_matches.get_one::<String>("NEW_NAME");
+                    command::rename::rename_file(
+                        search,
+                        new_name.map(String::as_str),
+                        sub_matches.get_flag("keep-extension"),
+                        &mut api_client,
+                    )
+                    .await?;
                 }
 
                 Some(("get", sub_matches)) => {
@@ -56,6 +195,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     command::get::download_file(search, &mut api_client).await?;
                 }
 
+                Some(("info", sub_matches)) => {
+                    let default: String = "".to_string();
+                    let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
+                    let json = sub_matches.get_flag("json");
+                    command::info::file_info(search, json, &mut api_client).await?;
+                }
+
                 Some(("remove", sub_matches)) => {
                     let default: String = "".to_string();
                     //TODO: todo rename search to filter
@@ -64,17 +210,194 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
 
                 Some(("visibility", sub_matches)) => {
+                    let targets = sub_matches
+                        .get_many::<String>("FILTER")
+                        .unwrap_or_default()
+                        .cloned()
+                        .collect::<Vec<String>>();
+                    let visibility = if sub_matches.get_flag("public") {
+                        Some(Visibility::Public)
+                    } else if sub_matches.get_flag("private") {
+                        Some(Visibility::Private)
+                    } else {
+                        None
+                    };
+
+                    match visibility {
+                        Some(visibility) => {
+                            let targets = if targets.is_empty() {
+                                vec!["".to_string()]
+                            } else {
+                                targets
+                            };
+                            command::visibility::set_file_visibility(
+                                &targets,
+                                visibility,
+                                &mut api_client,
+                            )
+                            .await?;
+                        }
+                        None if targets.len() > 1 => {
+                            return Err(std::io::Error::other(
+                                "Use --public or --private to change several files at once",
+                            )
+                            .into());
+                        }
+                        None => {
+                            let search = targets.first().map(String::as_str).unwrap_or_default();
+                            command::visibility::toggle_file_visibility(search, &mut api_client)
+                                .await?;
+                        }
+                    }
+                }
+
+                Some(("new", sub_matches)) => {
+                    let visibility = if sub_matches.get_flag("public") {
+                        Visibility::Public
+                    } else if sub_matches.get_flag("private") {
+                        Visibility::Private
+                    } else {
+                        api_client.preferences().default_visibility()
+                    };
+                    let link = command::new::new_file(
+                        sub_matches.get_one::<String>("name").map(String::as_str),
+                        sub_matches.get_one::<String>("lang").map(String::as_str),
+                        UploadOptions {
+                            allow_secrets: sub_matches.get_flag("allow-secrets"),
+                            force_new: sub_matches.get_flag("force-new"),
+                            encrypt: sub_matches.get_flag("encrypt"),
+                            ..UploadOptions::new(visibility)
+                        },
+                        &mut api_client,
+                    )
+                    .await?;
+                    if let Some(link) = link {
+                        if sub_matches.get_flag("qr") {
+                            print_qr(&link);
+                        }
+                        if sub_matches.get_flag("copy") {
+                            copy_link(&link);
+                        }
+                    }
+                }
+
+                Some(("link", sub_matches)) => {
                     let default: String = "".to_string();
-                    //TODO: todo rename search to filter
                     let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::visibility::toggle_file_visibility(search, &mut api_client).await?;
+                    command::link::file_link(search, &mut api_client).await?;
                 }
 
+                Some(("qr", sub_matches)) => {
+                    let default: String = "".to_string();
+                    let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
+                    command::qr::file_qr(search, &mut api_client).await?;
+                }
+
+                Some(("ui", _)) => command::ui::run_ui(&mut api_client).await?,
+
+                Some(("share", sub_matches)) => match sub_matches.subcommand() {
+                    Some(("list", sub_matches)) => {
+                        let filter = sub_matches.get_one::<String>("FILTER");
+                        command::share::list_share_links(
+                            filter.map(String::as_str),
+                            &mut api_client,
+                        )
+                        .await?;
+                    }
+                    Some(("revoke", sub_matches)) => {
+                        let link = sub_matches.get_one::<String>("LINK");
+                        command::share::revoke_share_link(
+                            link.map(String::as_str),
+                            &mut api_client,
+                        )
+                        .await?;
+                    }
+                    Some(("access", sub_matches)) => {
+                        let default: String = "".to_string();
+                        let filter = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
+                        command::share::list_file_access(filter, &mut api_client).await?;
+                    }
+                    Some(("revoke-access", sub_matches)) => {
+                        let default: String = "".to_string();
+                        let filter = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
+                        let email = sub_matches.get_one::<String>("with");
+                        command::share::revoke_file_access(
+                            filter,
+                            email.map(String::as_str),
+                            &mut api_client,
+                        )
+                        .await?;
+                    }
+                    _ if sub_matches.contains_id("with") => {
+                        let default: String = "".to_string();
+                        let file = sub_matches.get_one::<String>("FILE").unwrap_or(&default);
+                        let emails = sub_matches
+                            .get_many::<String>("with")
+                            .unwrap_or_default()
+                            .cloned()
+                            .collect::<Vec<String>>();
+                        let role = sub_matches.get_one::<String>("role").expect("defaulted");
+                        command::share::share_with_users(file, &emails, role, &mut api_client)
+                            .await?;
+                    }
+                    _ => {
+                        let default: String = "".to_string();
+                        let file = sub_matches.get_one::<String>("FILE").unwrap_or(&default);
+                        let expires_in = sub_matches.get_one::<Duration>("expires").copied();
+                        let max_downloads = sub_matches.get_one::<u64>("max-downloads").copied();
+                        command::share::create_share_link(
+                            file,
+                            expires_in,
+                            max_downloads,
+                            sub_matches.get_flag("password"),
+                            &mut api_client,
+                        )
+                        .await?;
+                    }
+                },
+
                 Some(("list", sub_matches)) => {
                     let default: String = "".to_string();
                     //TODO: todo rename search to filter
                     let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::list::list_files(search, &mut api_client).await?;
+                    let shared_with_me = sub_matches.get_flag("shared-with-me");
+                    let filters = FileFilters {
+                        sort: sub_matches
+                            .get_one::<String>("sort")
+                            .and_then(|sort| SortField::parse(sort)),
+                        reverse: sub_matches.get_flag("reverse"),
+                        file_type: sub_matches.get_one::<String>("type").cloned(),
+                        is_public: if sub_matches.get_flag("public") {
+                            Some(true)
+                        } else if sub_matches.get_flag("private") {
+                            Some(false)
+                        } else {
+                            None
+                        },
+                        status: sub_matches.get_one::<String>("status").cloned(),
+                        since: sub_matches.get_one::<DateTime<Utc>>("since").copied(),
+                        before: sub_matches.get_one::<DateTime<Utc>>("before").copied(),
+                        min_size: sub_matches.get_one::<u64>("min-size").copied(),
+                        max_size: sub_matches.get_one::<u64>("max-size").copied(),
+                        checksum: sub_matches.get_one::<String>("checksum").cloned(),
+                    };
+                    let output = if sub_matches.get_flag("names-only") {
+                        ListOutput::Names
+                    } else if sub_matches.get_flag("long") {
+                        ListOutput::Long
+                    } else if sub_matches.get_flag("table") || !console::Term::stdout().is_term() {
+                        ListOutput::Table
+                    } else {
+                        ListOutput::Interactive
+                    };
+                    command::list::list_files(
+                        search,
+                        shared_with_me,
+                        &filters,
+                        output,
+                        &mut api_client,
+                    )
+                    .await?;
                 }
 
                 _ => println!("Command not found."),
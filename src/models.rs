@@ -26,6 +26,35 @@ pub struct ShcFile {
     pub user_id: String,
     pub download_url: Option<String>,
     pub upload_status: String,
+    #[serde(default)]
+    pub download_count: Option<u64>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// hex encoded SHA-256 of the stored content, the ciphertext of encrypted files
+    #[serde(default)]
+    pub checksum: Option<String>,
+    #[serde(default)]
+    pub owner: Option<User>,
+    /// set when the file is a portion of a local file
+    #[serde(default)]
+    pub snippet: Option<SnippetSource>,
+    /// encrypted on the client, the server only has ciphertext
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+/// 1-based, inclusive range of lines.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Where a snippet was cut from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SnippetSource {
+    pub path: String,
+    pub lines: Vec<LineRange>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -46,3 +75,35 @@ pub struct AddFileResponse {
     pub file_name: String,
     pub is_public: bool,
 }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShareLink {
+    pub id: String,
+    pub file_id: String,
+    pub file_name: String,
+    pub expires_at: Option<String>,
+    pub max_downloads: Option<u64>,
+    pub download_count: u64,
+    pub password_protected: bool,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShareLinkResponse {
+    pub results: Vec<ShareLink>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileGrant {
+    pub id: String,
+    pub file_id: String,
+    pub file_name: String,
+    pub email: String,
+    pub role: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileGrantResponse {
+    pub results: Vec<FileGrant>,
+}
@@ -0,0 +1,345 @@
+use serde::{Deserialize, Serialize};
+use std::io::Error;
+use std::path::PathBuf;
+use zip::CompressionMethod;
+
+use crate::date::DateFormat;
+use crate::size::{parse_size, SizeFormat, SizeUnits};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArchiveFormat {
+    ZipBzip2,
+    ZipDeflate,
+    ZipStored,
+}
+
+impl ArchiveFormat {
+    pub fn compression_method(&self) -> CompressionMethod {
+        match self {
+            ArchiveFormat::ZipBzip2 => CompressionMethod::Bzip2,
+            ArchiveFormat::ZipDeflate => CompressionMethod::Deflated,
+            ArchiveFormat::ZipStored => CompressionMethod::Stored,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Name,
+    Size,
+    Updated,
+    Visibility,
+    Status,
+    Downloads,
+    Type,
+    Link,
+}
+
+impl Column {
+    pub fn parse_list(columns: &str) -> Result<Vec<Column>, Error> {
+        let columns = columns
+            .split(',')
+            .map(|column| match column.trim() {
+                "name" => Ok(Column::Name),
+                "size" => Ok(Column::Size),
+                "updated" => Ok(Column::Updated),
+                "visibility" => Ok(Column::Visibility),
+                "status" => Ok(Column::Status),
+                "downloads" => Ok(Column::Downloads),
+                "type" => Ok(Column::Type),
+                "link" => Ok(Column::Link),
+                column => Err(Error::other(format!(
+                    "unknown column \"{}\", expected name, size, updated, visibility, status, downloads, type or link",
+                    column
+                ))),
+            })
+            .collect::<Result<Vec<Column>, Error>>()?;
+
+        if !columns.contains(&Column::Name) {
+            return Err(Error::other("columns must include name"));
+        }
+        Ok(columns)
+    }
+}
+
+/// User preferences, every field falls back to its default when unset.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Preferences {
+    pub default_visibility: Option<Visibility>,
+    pub download_dir: Option<PathBuf>,
+    pub archive_format: Option<ArchiveFormat>,
+    pub output_format: Option<OutputFormat>,
+    pub confirm: Option<bool>,
+    pub color: Option<ColorChoice>,
+    pub page_size: Option<u32>,
+    pub columns: Option<String>,
+    pub size_units: Option<SizeUnits>,
+    pub size_precision: Option<u32>,
+    pub date_format: Option<DateFormat>,
+    pub limit_rate: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub enum PreferenceKind {
+    Bool,
+    Integer,
+    String,
+}
+
+pub struct PreferenceSchema {
+    pub key: &'static str,
+    pub kind: PreferenceKind,
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+pub const PREFERENCES_SCHEMA: &[PreferenceSchema] = &[
+    PreferenceSchema {
+        key: "default_visibility",
+        kind: PreferenceKind::String,
+        default: "private",
+        description: "visibility of new uploads (public, private)",
+    },
+    PreferenceSchema {
+        key: "download_dir",
+        kind: PreferenceKind::String,
+        default: ".",
+        description: "directory downloads are saved to",
+    },
+    PreferenceSchema {
+        key: "archive_format",
+        kind: PreferenceKind::String,
+        default: "zip-bzip2",
+        description: "archive used for folder uploads (zip-bzip2, zip-deflate, zip-stored)",
+    },
+    PreferenceSchema {
+        key: "output_format",
+        kind: PreferenceKind::String,
+        default: "human",
+        description: "output of file details (human, json)",
+    },
+    PreferenceSchema {
+        key: "confirm",
+        kind: PreferenceKind::Bool,
+        default: "true",
+        description: "ask for confirmation before changing files (true, false)",
+    },
+    PreferenceSchema {
+        key: "color",
+        kind: PreferenceKind::String,
+        default: "auto",
+        description: "colored output (auto, always, never)",
+    },
+    PreferenceSchema {
+        key: "page_size",
+        kind: PreferenceKind::Integer,
+        default: "100",
+        description: "number of files fetched per request (1-100)",
+    },
+    PreferenceSchema {
+        key: "columns",
+        kind: PreferenceKind::String,
+        default: "name,size,updated,visibility",
+        description:
+            "file table columns (name, size, updated, visibility, status, downloads, type, link)",
+    },
+    PreferenceSchema {
+        key: "size_units",
+        kind: PreferenceKind::String,
+        default: "decimal",
+        description: "units of file sizes, decimal (kB, MB) or binary (KiB, MiB)",
+    },
+    PreferenceSchema {
+        key: "size_precision",
+        kind: PreferenceKind::Integer,
+        default: "2",
+        description: "decimals shown in file sizes (0-3)",
+    },
+    PreferenceSchema {
+        key: "date_format",
+        kind: PreferenceKind::String,
+        default: "relative",
+        description: "how dates are shown, relative (3 days ago), absolute or iso",
+    },
+    PreferenceSchema {
+        key: "limit_rate",
+        kind: PreferenceKind::String,
+        default: "0",
+        description: "maximum transfer speed per second, e.g. 2M or 500kB (0 for no limit)",
+    },
+];
+
+pub fn preference_schema(key: &str) -> Result<&'static PreferenceSchema, Error> {
+    PREFERENCES_SCHEMA
+        .iter()
+        .find(|schema| schema.key == key)
+        .ok_or_else(|| {
+            Error::other(format!(
+                "Unknown config key \"{}\", run `shc config list` to see all keys",
+                key
+            ))
+        })
+}
+
+impl Preferences {
+    pub fn default_visibility(&self) -> Visibility {
+        self.default_visibility.unwrap_or(Visibility::Private)
+    }
+
+    pub fn download_dir(&self) -> PathBuf {
+        self.download_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    pub fn archive_format(&self) -> ArchiveFormat {
+        self.archive_format.unwrap_or(ArchiveFormat::ZipBzip2)
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format.unwrap_or(OutputFormat::Human)
+    }
+
+    pub fn confirm(&self) -> bool {
+        self.confirm.unwrap_or(true)
+    }
+
+    pub fn color(&self) -> ColorChoice {
+        self.color.unwrap_or(ColorChoice::Auto)
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size.unwrap_or(100)
+    }
+
+    pub fn columns(&self) -> Vec<Column> {
+        self.columns
+            .as_deref()
+            .and_then(|columns| Column::parse_list(columns).ok())
+            .unwrap_or_else(|| {
+                vec![
+                    Column::Name,
+                    Column::Size,
+                    Column::Updated,
+                    Column::Visibility,
+                ]
+            })
+    }
+
+    pub fn size_format(&self) -> SizeFormat {
+        SizeFormat {
+            units: self.size_units.unwrap_or(SizeUnits::Decimal),
+            precision: self.size_precision.unwrap_or(2) as usize,
+        }
+    }
+
+    pub fn date_format(&self) -> DateFormat {
+        self.date_format.unwrap_or(DateFormat::Relative)
+    }
+
+    /// Bytes per second transfers are limited to, `None` when they aren't.
+    pub fn limit_rate(&self) -> Option<u64> {
+        self.limit_rate
+            .as_deref()
+            .and_then(|rate| parse_size(rate).ok())
+            .filter(|&rate| rate > 0)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(rate) = &self.limit_rate {
+            parse_size(rate).map_err(Error::other)?;
+        }
+        if self.size_precision.is_some_and(|precision| precision > 3) {
+            return Err(Error::other("size_precision must be between 0 and 3"));
+        }
+        if let Some(columns) = &self.columns {
+            Column::parse_list(columns)?;
+        }
+        if let Some(page_size) = self.page_size {
+            if !(1..=100).contains(&page_size) {
+                return Err(Error::other("page_size must be between 1 and 100"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_table(&self) -> Result<toml::Table, Box<dyn std::error::Error>> {
+        match toml::Value::try_from(self)? {
+            toml::Value::Table(table) => Ok(table),
+            _ => Err(Error::other("Preferences must serialize to a table").into()),
+        }
+    }
+
+    pub fn from_table(table: toml::Table) -> Result<Preferences, Box<dyn std::error::Error>> {
+        let preferences: Preferences = table.try_into()?;
+        preferences.validate()?;
+        Ok(preferences)
+    }
+
+    /// Stored value of `key`, `None` when it is unset.
+    pub fn get(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        preference_schema(key)?;
+        Ok(self.to_table()?.get(key).map(display_value))
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let schema = preference_schema(key)?;
+        let value = match schema.kind {
+            PreferenceKind::Bool => toml::Value::Boolean(value.parse().map_err(|_| {
+                Error::other(format!("{} expects true or false, got \"{}\"", key, value))
+            })?),
+            PreferenceKind::Integer => toml::Value::Integer(value.parse().map_err(|_| {
+                Error::other(format!("{} expects a number, got \"{}\"", key, value))
+            })?),
+            PreferenceKind::String => toml::Value::String(value.to_string()),
+        };
+
+        let mut table = self.to_table()?;
+        table.insert(key.to_string(), value);
+        *self = Preferences::from_table(table).map_err(|e| {
+            let reason = e.to_string();
+            let reason = reason.lines().next().unwrap_or_default().to_string();
+            Error::other(format!("Invalid value for {}: {}", key, reason))
+        })?;
+        Ok(())
+    }
+
+    pub fn unset(&mut self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+        preference_schema(key)?;
+        let mut table = self.to_table()?;
+        table.remove(key);
+        *self = Preferences::from_table(table)?;
+        Ok(())
+    }
+}
+
+fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Bytes that may go through at full speed after a pause, as a fraction of a second's worth.
+const BURST_SECONDS: f64 = 0.25;
+
+struct Bucket {
+    /// goes negative when a chunk is bigger than what's left, the debt is slept off
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket limiting transfers to `bytes_per_sec`. Clones share the
+/// bucket, so concurrent transfers split the limit instead of each getting all of it.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bytes_per_sec: u64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_sec: u64) -> RateLimiter {
+        RateLimiter {
+            bytes_per_sec,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Waits until `bytes` more may be sent or received.
+    pub async fn acquire(&self, bytes: u64) {
+        let rate = self.bytes_per_sec as f64;
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.last_refill).as_secs_f64() * rate;
+            bucket.tokens = (bucket.tokens + refill).min(rate * BURST_SECONDS);
+            bucket.last_refill = now;
+            bucket.tokens -= bytes as f64;
+            if bucket.tokens < 0.0 {
+                Duration::from_secs_f64(-bucket.tokens / rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
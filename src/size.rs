@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SizeUnits {
+    /// powers of 1000: kB, MB, GB
+    Decimal,
+    /// powers of 1024: KiB, MiB, GiB
+    Binary,
+}
+
+const DECIMAL_UNITS: [&str; 9] = ["B", "kB", "MB", "GB", "TB", "PB", "EB", "ZB", "YB"];
+const BINARY_UNITS: [&str; 9] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeFormat {
+    pub units: SizeUnits,
+    /// decimals shown for anything bigger than a byte
+    pub precision: usize,
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        SizeFormat {
+            units: SizeUnits::Decimal,
+            precision: 2,
+        }
+    }
+}
+
+impl SizeFormat {
+    pub fn format(&self, bytes: u64) -> String {
+        let (base, units) = match self.units {
+            SizeUnits::Decimal => (1000.0, DECIMAL_UNITS),
+            SizeUnits::Binary => (1024.0, BINARY_UNITS),
+        };
+
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= base && unit < units.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+
+        // 999_999 bytes would otherwise round up to "1000.00 kB"
+        let factor = 10f64.powi(self.precision as i32);
+        if (value * factor).round() / factor >= base && unit < units.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            format!("{} {}", bytes, units[0])
+        } else {
+            format!("{:.*} {}", self.precision, value, units[unit])
+        }
+    }
+}
+
+/// Parses sizes like `1500`, `20kB`, `1.5 GB` or `50MiB`. Units are case
+/// insensitive, `k`/`M`/`G` without a `B` are decimal.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let unit_start = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(unit_start);
+
+    let amount = amount
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid size \"{}\", expected e.g. 500kB or 50MiB", input))?;
+
+    let unit = unit.trim().to_lowercase();
+    let (prefix, binary) = match unit.strip_suffix("ib") {
+        Some(prefix) => (prefix, true),
+        None => (unit.strip_suffix('b').unwrap_or(&unit), false),
+    };
+    let exponent = match prefix {
+        "" if !binary => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        _ => return Err(format!("Invalid size unit in \"{}\"", input)),
+    };
+    let base: f64 = if binary { 1024.0 } else { 1000.0 };
+
+    let bytes = amount * base.powi(exponent);
+    if bytes > u64::MAX as f64 {
+        return Err(format!("Size \"{}\" is too big", input));
+    }
+    Ok(bytes.round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINARY: SizeFormat = SizeFormat {
+        units: SizeUnits::Binary,
+        precision: 2,
+    };
+
+    #[test]
+    fn formats_decimal_and_binary() {
+        let decimal = SizeFormat::default();
+        assert_eq!(decimal.format(0), "0 B");
+        assert_eq!(decimal.format(999), "999 B");
+        assert_eq!(decimal.format(1000), "1.00 kB");
+        assert_eq!(decimal.format(1_500_000), "1.50 MB");
+        assert_eq!(BINARY.format(1023), "1023 B");
+        assert_eq!(BINARY.format(1024), "1.00 KiB");
+        assert_eq!(BINARY.format(50 * 1024 * 1024), "50.00 MiB");
+    }
+
+    #[test]
+    fn rounding_moves_to_the_next_unit() {
+        let decimal = SizeFormat::default();
+        assert_eq!(decimal.format(999_999), "1.00 MB");
+        assert_eq!(decimal.format(999_994), "999.99 kB");
+        assert_eq!(BINARY.format(1024 * 1024 - 1), "1.00 MiB");
+
+        let whole = SizeFormat {
+            units: SizeUnits::Decimal,
+            precision: 0,
+        };
+        assert_eq!(whole.format(999_500), "1 MB");
+        assert_eq!(whole.format(999_499), "999 kB");
+    }
+
+    #[test]
+    fn largest_unit_doesnt_overflow() {
+        assert_eq!(SizeFormat::default().format(u64::MAX), "18.45 EB");
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("1500"), Ok(1500));
+        assert_eq!(parse_size("20kB"), Ok(20_000));
+        assert_eq!(parse_size("1.5 GB"), Ok(1_500_000_000));
+        assert_eq!(parse_size("2M"), Ok(2_000_000));
+        assert_eq!(parse_size("50MiB"), Ok(50 * 1024 * 1024));
+        assert_eq!(parse_size("1kib"), Ok(1024));
+        assert_eq!(parse_size(" 10 b "), Ok(10));
+        assert_eq!(parse_size("0.5kB"), Ok(500));
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("fast").is_err());
+        assert!(parse_size("10 XB").is_err());
+        assert!(parse_size("10iB").is_err());
+        assert!(parse_size("1.2.3MB").is_err());
+        assert!(parse_size("99999999999PB").is_err());
+    }
+}
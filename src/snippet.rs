@@ -0,0 +1,282 @@
+use regex::Regex;
+use std::io::Error;
+use std::path::Path;
+
+use crate::models::{LineRange, SnippetSource};
+
+/// Which part of a file to upload.
+pub enum Selection {
+    Lines(LineRange),
+    /// lines matching the pattern, with that many lines of context around each
+    Grep(Regex, usize),
+}
+
+/// Parses `20-45`, `20` (just that line) or `20-` (up to the end of the file).
+pub fn parse_line_range(input: &str) -> Result<LineRange, String> {
+    let invalid = || {
+        format!(
+            "Invalid line range \"{}\", expected e.g. 20-45, 20 or 20-",
+            input
+        )
+    };
+    let (start, end) = match input.trim().split_once('-') {
+        Some((start, "")) => (start, None),
+        Some((start, end)) => (start, Some(end)),
+        None => (input.trim(), Some(input.trim())),
+    };
+
+    let start = start.trim().parse::<usize>().map_err(|_| invalid())?;
+    let end = match end {
+        Some(end) => end.trim().parse::<usize>().map_err(|_| invalid())?,
+        None => usize::MAX,
+    };
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    Ok(LineRange { start, end })
+}
+
+/// Splits `src/main.rs:20-45` into the path and the line range, `None` when
+/// there's no range after the path.
+pub fn split_path_range(input: &str) -> Option<(&str, LineRange)> {
+    let (path, range) = input.rsplit_once(':')?;
+    if path.is_empty() {
+        return None;
+    }
+    parse_line_range(range).ok().map(|range| (path, range))
+}
+
+pub struct Snippet {
+    /// name of the uploaded file, e.g. `main_L20-45.rs`
+    pub file_name: String,
+    pub content: String,
+    pub source: SnippetSource,
+}
+
+/// Merges ranges that overlap or touch, `ranges` must be sorted by start.
+fn merge_ranges(ranges: Vec<LineRange>) -> Vec<LineRange> {
+    let mut merged: Vec<LineRange> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + 1 => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Longest file name most file systems allow.
+const MAX_FILE_NAME_BYTES: usize = 255;
+
+/// The longest prefix of `text` that fits in `max_bytes` without splitting a character.
+fn truncate_bytes(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Cuts the selected lines out of `path`. `display_path` is recorded as the
+/// origin, so it should be the path the user typed rather than an absolute one.
+pub fn cut_snippet(
+    path: &Path,
+    display_path: &str,
+    selection: &Selection,
+) -> Result<Snippet, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        Error::other(format!(
+            "Cannot read {} as text, only text files can be shared in part: {}",
+            display_path, e
+        ))
+    })?;
+    let lines: Vec<&str> = text.lines().collect();
+
+    let ranges = match selection {
+        Selection::Lines(range) => {
+            if range.start > lines.len() {
+                return Err(Error::other(format!(
+                    "{} has only {} lines",
+                    display_path,
+                    lines.len()
+                ))
+                .into());
+            }
+            vec![LineRange {
+                start: range.start,
+                end: range.end.min(lines.len()),
+            }]
+        }
+        Selection::Grep(pattern, context) => {
+            let ranges = lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| pattern.is_match(line))
+                .map(|(i, _)| LineRange {
+                    start: (i + 1).saturating_sub(*context).max(1),
+                    end: (i + 1 + context).min(lines.len()),
+                })
+                .collect::<Vec<LineRange>>();
+            if ranges.is_empty() {
+                return Err(Error::other(format!(
+                    "No lines in {} match \"{}\"",
+                    display_path, pattern
+                ))
+                .into());
+            }
+            merge_ranges(ranges)
+        }
+    };
+
+    // separate the hunks like grep does
+    let content = ranges
+        .iter()
+        .map(|range| {
+            let mut hunk = lines[range.start - 1..range.end].join("\n");
+            hunk.push('\n');
+            hunk
+        })
+        .collect::<Vec<String>>()
+        .join("--\n");
+
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("snippet");
+    // one range per hunk would outgrow the file name limit with enough matches
+    let first = ranges.first().expect("at least one range");
+    let last = ranges.last().expect("at least one range");
+    let suffix = if ranges.len() == 1 {
+        format!("L{}-{}", first.start, first.end)
+    } else {
+        format!("L{}-{}_{}hunks", first.start, last.end, ranges.len())
+    };
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| format!(".{}", extension))
+        .unwrap_or_default();
+    let stem = truncate_bytes(
+        stem,
+        MAX_FILE_NAME_BYTES.saturating_sub(suffix.len() + extension.len() + 1),
+    );
+    let file_name = format!("{}_{}{}", stem, suffix, extension);
+
+    Ok(Snippet {
+        file_name,
+        content,
+        source: SnippetSource {
+            path: display_path.to_string(),
+            lines: ranges,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: usize, end: usize) -> LineRange {
+        LineRange { start, end }
+    }
+
+    /// Writes `text` to `name` in a fresh folder and cuts `selection` out of it.
+    fn cut(name: &str, text: &str, selection: &Selection) -> Snippet {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, text).unwrap();
+        cut_snippet(&path, name, selection).unwrap()
+    }
+
+    #[test]
+    fn parses_line_ranges() {
+        assert_eq!(parse_line_range("20-45"), Ok(range(20, 45)));
+        assert_eq!(parse_line_range(" 20 - 45 "), Ok(range(20, 45)));
+        assert_eq!(parse_line_range("20"), Ok(range(20, 20)));
+        assert_eq!(parse_line_range("20-"), Ok(range(20, usize::MAX)));
+        assert!(parse_line_range("0-5").is_err());
+        assert!(parse_line_range("45-20").is_err());
+        assert!(parse_line_range("-5").is_err());
+        assert!(parse_line_range("a-b").is_err());
+        assert!(parse_line_range("").is_err());
+    }
+
+    #[test]
+    fn splits_path_and_range() {
+        assert_eq!(
+            split_path_range("src/main.rs:20-45"),
+            Some(("src/main.rs", range(20, 45)))
+        );
+        assert_eq!(
+            split_path_range("C:/notes.txt:3"),
+            Some(("C:/notes.txt", range(3, 3)))
+        );
+        assert_eq!(split_path_range("src/main.rs"), None);
+        assert_eq!(split_path_range("notes:draft.txt"), None);
+        assert_eq!(split_path_range(":20-45"), None);
+    }
+
+    #[test]
+    fn cuts_a_line_range() {
+        let snippet = cut(
+            "main.rs",
+            "one\ntwo\nthree\nfour\n",
+            &Selection::Lines(range(2, usize::MAX)),
+        );
+        assert_eq!(snippet.content, "two\nthree\nfour\n");
+        assert_eq!(snippet.file_name, "main_L2-4.rs");
+        assert_eq!(snippet.source.lines, [range(2, 4)]);
+    }
+
+    #[test]
+    fn merges_grep_ranges_that_overlap_or_touch() {
+        let text = (1..=20)
+            .map(|i| {
+                if [3, 5, 15].contains(&i) {
+                    "match"
+                } else {
+                    "-"
+                }
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let snippet = cut(
+            "log.txt",
+            &text,
+            &Selection::Grep(Regex::new("match").unwrap(), 1),
+        );
+        assert_eq!(snippet.source.lines, [range(2, 6), range(14, 16)]);
+        assert_eq!(snippet.content, "-\nmatch\n-\nmatch\n-\n--\n-\nmatch\n-\n");
+        assert_eq!(snippet.file_name, "log_L2-16_2hunks.txt");
+
+        assert_eq!(
+            merge_ranges(vec![range(1, 3), range(4, 5), range(7, 8)]),
+            [range(1, 5), range(7, 8)]
+        );
+        assert_eq!(
+            merge_ranges(vec![range(1, 10), range(2, 3)]),
+            [range(1, 10)]
+        );
+    }
+
+    #[test]
+    fn caps_the_file_name_length() {
+        // as long as the file system allows, so the suffix has to take bytes off the stem
+        let name = format!("{}.txt", "é".repeat(125));
+        let snippet = cut(
+            &name,
+            "match\n-\nmatch\n",
+            &Selection::Grep(Regex::new("match").unwrap(), 0),
+        );
+        assert!(snippet.file_name.len() <= MAX_FILE_NAME_BYTES);
+        assert!(snippet.file_name.starts_with("éé"));
+        assert!(snippet.file_name.ends_with("_L1-3_2hunks.txt"));
+
+        assert_eq!(truncate_bytes("héllo", 2), "h");
+        assert_eq!(truncate_bytes("héllo", 3), "hé");
+        assert_eq!(truncate_bytes("héllo", 10), "héllo");
+    }
+}
@@ -1,60 +1,642 @@
-use chrono::DateTime;
-use dialoguer::{theme, Select};
+use console::{style, Key, Term};
+use dialoguer::{theme, Confirm, MultiSelect};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::consts::MAX_NAME_WIDTH_LENGTH;
+use crate::api_client::ApiClient;
+use crate::command::link::share_link;
+use crate::consts::MIN_NAME_WIDTH;
+use crate::date::DateFormat;
+use crate::keystore::Keystore;
 use crate::models::ShcFile;
-use crate::utils::format_bytes;
+use crate::preferences::{Column, Preferences};
+use crate::rate_limit::RateLimiter;
+use crate::size::SizeFormat;
 
-pub fn shc_file_input(files: &[ShcFile], prompt: &str) -> usize {
-    let size_width = 10;
-    let updated_at_width = 20;
-    let visibility_width = 10;
-    let date_formatter = timeago::Formatter::new();
+/// Cuts `text` down to `width` columns by replacing its middle with an ellipsis.
+/// Returns the text, how many leading chars were kept and where the kept tail starts.
+fn truncate_middle(text: &str, width: usize) -> (String, usize, usize) {
+    let chars = text.chars().collect::<Vec<char>>();
+    if UnicodeWidthStr::width(text) <= width {
+        return (text.to_string(), chars.len(), chars.len());
+    }
 
-    let files =
+    let available = width.saturating_sub(1);
+    let head_width = available.div_ceil(2);
+    let tail_width = available - head_width;
+
+    let mut head_len = 0;
+    let mut used = 0;
+    for c in &chars {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width > head_width {
+            break;
+        }
+        used += c_width;
+        head_len += 1;
+    }
+
+    let mut tail_start = chars.len();
+    let mut used = 0;
+    while tail_start > head_len {
+        let c_width = chars[tail_start - 1].width().unwrap_or(0);
+        if used + c_width > tail_width {
+            break;
+        }
+        used += c_width;
+        tail_start -= 1;
+    }
+
+    let truncated = chars[..head_len]
+        .iter()
+        .chain(['…'].iter())
+        .chain(chars[tail_start..].iter())
+        .collect::<String>();
+    (truncated, head_len, tail_start)
+}
+
+fn pad(text: String, visible_width: usize, width: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(width.saturating_sub(visible_width))
+    )
+}
+
+/// Column layout of file tables. In a picker the name column takes whatever
+/// width the terminal has left after the other columns, and columns that don't
+/// fit are left out. Printed tables fit every column to its content instead.
+pub struct FileTable {
+    columns: Vec<Column>,
+    widths: Vec<usize>,
+    size_format: SizeFormat,
+    date_format: DateFormat,
+    /// keys of encrypted files, so their links can be opened
+    keystore: Option<Keystore>,
+}
+
+impl FileTable {
+    fn new(files: &[ShcFile], api_client: &ApiClient, prefix_width: usize) -> FileTable {
+        let preferences = api_client.preferences();
+        let mut table = FileTable {
+            columns: preferences.columns(),
+            widths: vec![],
+            size_format: preferences.size_format(),
+            date_format: preferences.date_format(),
+            keystore: Keystore::load(api_client.keystore_path()).ok(),
+        };
+        let term_width = match Term::stderr().size_checked() {
+            Some((_, width)) => width as usize,
+            None => 80,
+        };
+        // wrapped rows can't be cleared, so the columns that don't fit next
+        // to the narrowest name column are left out
+        let budget = term_width.saturating_sub(prefix_width + MIN_NAME_WIDTH + 1);
+        let mut other_width = 0;
+        let columns = table
+            .columns
+            .iter()
+            .copied()
+            .filter(|&column| {
+                if column == Column::Name {
+                    return true;
+                }
+                let width = table.column_width(files, column) + 2;
+                if other_width + width > budget {
+                    return false;
+                }
+                other_width += width;
+                true
+            })
+            .collect();
+        table.columns = columns;
+        let longest_name = table.content_width(files, Column::Name);
+
+        let available = term_width.saturating_sub(prefix_width + other_width + 1);
+        table.widths = table
+            .columns
+            .iter()
+            .map(|&column| match column {
+                Column::Name => longest_name.min(available).max(MIN_NAME_WIDTH),
+                column => table.column_width(files, column),
+            })
+            .collect();
+        table
+    }
+
+    pub fn fit_content(
+        files: &[ShcFile],
+        columns: Vec<Column>,
+        api_client: &ApiClient,
+    ) -> FileTable {
+        let preferences = api_client.preferences();
+        let mut table = FileTable {
+            columns,
+            widths: vec![],
+            size_format: preferences.size_format(),
+            date_format: preferences.date_format(),
+            keystore: Keystore::load(api_client.keystore_path()).ok(),
+        };
+        table.widths = table
+            .columns
+            .iter()
+            .map(|&column| table.content_width(files, column))
+            .collect();
+        table
+    }
+
+    /// Width of a column in a picker. Links to encrypted files carry their
+    /// key, so that column grows to fit them rather than cutting keys short.
+    fn column_width(&self, files: &[ShcFile], column: Column) -> usize {
+        match column {
+            Column::Link => self.content_width(files, column),
+            column => Self::fixed_width(column, self.date_format),
+        }
+    }
+
+    fn fixed_width(column: Column, date_format: DateFormat) -> usize {
+        match column {
+            Column::Name => 0,
+            Column::Size => 10,
+            Column::Updated => date_format.width(),
+            Column::Visibility => 10,
+            Column::Status => 9,
+            Column::Downloads => 9,
+            Column::Type => 16,
+            Column::Link => 50,
+        }
+    }
+
+    fn content_width(&self, files: &[ShcFile], column: Column) -> usize {
         files
             .iter()
-            .map(|file| -> Result<String, Box<dyn std::error::Error>> {
-                let mut name = file.name.clone();
-                println!("{} {}", name, name.len());
-                if name.len() > MAX_NAME_WIDTH_LENGTH {
-                    name.truncate(name.len() - 5);
-                    name.push_str("...");
+            .map(|file| UnicodeWidthStr::width(self.text(file, column).as_str()))
+            .max()
+            .unwrap_or_default()
+            .max(Self::title(column).len())
+    }
+
+    fn title(column: Column) -> &'static str {
+        match column {
+            Column::Name => "Name",
+            Column::Size => "Size",
+            Column::Updated => "Updated",
+            Column::Visibility => "Visibility",
+            Column::Status => "Status",
+            Column::Downloads => "Downloads",
+            Column::Type => "Type",
+            Column::Link => "Link",
+        }
+    }
+
+    fn text(&self, file: &ShcFile, column: Column) -> String {
+        match column {
+            Column::Name => file.name.clone(),
+            Column::Size => self.size_format.format(file.size),
+            Column::Updated => self.date_format.format(&file.updated_at),
+            Column::Visibility => if file.is_public { "Public" } else { "Private" }.to_string(),
+            Column::Status => file.upload_status.clone(),
+            Column::Downloads => file
+                .download_count
+                .map(|count| count.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            Column::Type => file.mime_type.clone(),
+            Column::Link => {
+                let key = match &self.keystore {
+                    Some(keystore) if file.encrypted => keystore.get(&file.id),
+                    _ => None,
+                };
+                share_link(&file.id, key.as_ref())
+            }
+        }
+    }
+
+    pub fn header(&self) -> String {
+        self.columns
+            .iter()
+            .zip(&self.widths)
+            .map(|(&column, &width)| {
+                let title = Self::title(column);
+                pad(title.to_string(), title.len(), width)
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    }
+
+    pub fn row(&self, file: &ShcFile) -> String {
+        self.highlighted_row(file, None)
+    }
+
+    fn highlighted_row(&self, file: &ShcFile, fuzzy_match: Option<&FuzzyMatch>) -> String {
+        self.columns
+            .iter()
+            .zip(&self.widths)
+            .map(|(&column, &width)| {
+                if column == Column::Name {
+                    return Self::name_cell(file, fuzzy_match, width);
                 }
 
-                let time_ago = date_formatter.convert_chrono(
-                    DateTime::parse_from_rfc3339(file.updated_at.as_str())?,
-                    chrono::Utc::now(),
-                );
-                let size = format_bytes(file.size);
-                let visibility = if file.is_public {
-                    "Public".to_string()
+                let text = self.text(file, column);
+                let (text, _, _) = truncate_middle(&text, width);
+                let text_width = UnicodeWidthStr::width(text.as_str());
+                let visibility_matched =
+                    fuzzy_match.is_some_and(|fuzzy_match| fuzzy_match.visibility_matched);
+                if column == Column::Visibility && visibility_matched {
+                    return pad(style(text).cyan().bold().to_string(), text_width, width);
+                }
+                pad(text, text_width, width)
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    }
+
+    fn name_cell(file: &ShcFile, fuzzy_match: Option<&FuzzyMatch>, name_width: usize) -> String {
+        let (name, head_len, tail_start) = truncate_middle(&file.name, name_width);
+        let width = UnicodeWidthStr::width(name.as_str());
+        let name_indices = fuzzy_match
+            .map(|fuzzy_match| fuzzy_match.name_indices.as_slice())
+            .unwrap_or_default();
+        if name_indices.is_empty() {
+            return pad(name, width, name_width);
+        }
+
+        // map the matched chars of the full name onto the truncated one
+        let ellipsis_len = if head_len == tail_start { 0 } else { 1 };
+        let highlighted = name
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let original = if i < head_len {
+                    Some(i)
+                } else if i >= head_len + ellipsis_len {
+                    Some(i - head_len - ellipsis_len + tail_start)
                 } else {
-                    "Private".to_string()
+                    None
                 };
-                Ok(format!(
-                "{:<name_width$}\t\t{:<size_width$}\t{:<updated_at_width$}\t{:<visibility_width$}",
-                name, size, time_ago, visibility,
-                name_width = name.len(),
-                size_width = size_width,
-                updated_at_width = updated_at_width,
-                visibility_width = visibility_width
-            ))
+                match original {
+                    Some(original) if name_indices.contains(&original) => {
+                        style(c).cyan().bold().to_string()
+                    }
+                    _ => c.to_string(),
+                }
             })
-            .collect::<Result<Vec<String>, Box<dyn std::error::Error>>>();
+            .collect::<String>();
+        pad(highlighted, width, name_width)
+    }
+}
 
-    let files = match files {
-        Ok(items) => items,
-        Err(_) => vec![],
-    };
+struct FuzzyMatch {
+    index: usize,
+    score: i64,
+    name_indices: Vec<usize>,
+    visibility_matched: bool,
+}
+
+/// Fuzzy matches `query` against each file's name, extension and visibility.
+fn fuzzy_filter(files: &[ShcFile], query: &str) -> Vec<FuzzyMatch> {
+    let matcher = SkimMatcherV2::default().ignore_case();
+
+    let mut matches = files
+        .iter()
+        .enumerate()
+        .filter_map(|(index, file)| {
+            if query.is_empty() {
+                return Some(FuzzyMatch {
+                    index,
+                    score: 0,
+                    name_indices: vec![],
+                    visibility_matched: false,
+                });
+            }
 
-    let selection = Select::with_theme(&theme::ColorfulTheme::default())
+            let visibility = if file.is_public { "public" } else { "private" };
+            let name_len = file.name.chars().count();
+            let extension_len = file.extension.chars().count();
+            let haystack = format!("{} {} {}", file.name, file.extension, visibility);
+            let (score, indices) = matcher.fuzzy_indices(&haystack, query)?;
+
+            Some(FuzzyMatch {
+                index,
+                score,
+                name_indices: indices.iter().copied().filter(|&i| i < name_len).collect(),
+                visibility_matched: indices.iter().any(|&i| i > name_len + extension_len),
+            })
+        })
+        .collect::<Vec<FuzzyMatch>>();
+
+    // stable sort keeps the server order for equal scores
+    matches.sort_by_key(|fuzzy_match| std::cmp::Reverse(fuzzy_match.score));
+    matches
+}
+
+/// The picker loop of `shc_file_input`, keeping `drawn_lines` up to date so the
+/// caller can clear them however it ends. `None` when it's aborted.
+async fn pick_file(
+    term: &Term,
+    files: &mut Vec<ShcFile>,
+    prompt: &str,
+    api_client: &ApiClient,
+    drawn_lines: &mut usize,
+) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    let mut table = FileTable::new(files, api_client, 2);
+    let mut query = String::new();
+    let mut searched_queries: Vec<String> = vec![];
+    let mut cursor = 0;
+    let mut offset = 0;
+
+    loop {
+        let mut matches = fuzzy_filter(files, &query);
+
+        let already_searched = searched_queries
+            .iter()
+            .any(|searched| query.starts_with(searched.as_str()));
+        if matches.is_empty() && !query.is_empty() && !already_searched {
+            term.clear_last_lines(*drawn_lines)?;
+            term.write_line(&format!(
+                "{} {} {} {}",
+                style("?").yellow(),
+                style(prompt).bold(),
+                style("›").dim(),
+                style(format!("searching for \"{}\"...", query)).dim()
+            ))?;
+            *drawn_lines = 1;
+
+            searched_queries.push(query.clone());
+            let res = api_client.list_files(&query).await?;
+            let new_files = res
+                .results
+                .into_iter()
+                .filter(|file| files.iter().all(|known| known.id != file.id))
+                .collect::<Vec<ShcFile>>();
+            if !new_files.is_empty() {
+                // only an exhausted search is final, new files may match longer queries too
+                searched_queries.pop();
+                files.extend(new_files);
+                table = FileTable::new(files, api_client, 2);
+                matches = fuzzy_filter(files, &query);
+            }
+        }
+
+        let max_rows = (term.size().0 as usize).saturating_sub(3).clamp(1, 20);
+        cursor = cursor.min(matches.len().saturating_sub(1));
+        if cursor < offset {
+            offset = cursor;
+        } else if cursor >= offset + max_rows {
+            offset = cursor + 1 - max_rows;
+        }
+
+        term.clear_last_lines(*drawn_lines)?;
+        term.write_line(&format!(
+            "{} {} {} {}",
+            style("?").yellow(),
+            style(prompt).bold(),
+            style("›").dim(),
+            query
+        ))?;
+        term.write_line(&format!("  {}", style(table.header()).dim()))?;
+        *drawn_lines = 2;
+        if matches.is_empty() {
+            term.write_line(&style("  No matching files").dim().to_string())?;
+            *drawn_lines += 1;
+        }
+        for (i, fuzzy_match) in matches.iter().enumerate().skip(offset).take(max_rows) {
+            let row = table.highlighted_row(&files[fuzzy_match.index], Some(fuzzy_match));
+            if i == cursor {
+                term.write_line(&format!("{} {}", style("❯").green(), row))?;
+            } else {
+                term.write_line(&format!("  {}", row))?;
+            }
+            *drawn_lines += 1;
+        }
+
+        let key_term = term.clone();
+        let key = tokio::task::spawn_blocking(move || key_term.read_key()).await??;
+        match key {
+            Key::Enter if !matches.is_empty() => return Ok(Some(matches[cursor].index)),
+            Key::ArrowUp | Key::BackTab => cursor = cursor.saturating_sub(1),
+            Key::ArrowDown | Key::Tab if cursor + 1 < matches.len() => cursor += 1,
+            Key::Backspace => {
+                query.pop();
+                cursor = 0;
+            }
+            Key::Escape => {
+                query.clear();
+                cursor = 0;
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                cursor = 0;
+            }
+            Key::CtrlC => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Fuzzy-searchable file picker, typing filters the list and once nothing
+/// matches locally the backend is searched for the query. Files found that
+/// way are appended to `files`, the returned index points into it.
+pub async fn shc_file_input(
+    files: &mut Vec<ShcFile>,
+    prompt: &str,
+    api_client: &ApiClient,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let term = Term::stderr();
+    if !term.is_term() {
+        return Err(
+            Error::other("Cannot pick a file without a terminal, pass a filter instead").into(),
+        );
+    }
+
+    let mut drawn_lines = 0;
+    term.hide_cursor()?;
+    let selection = pick_file(&term, files, prompt, api_client, &mut drawn_lines).await;
+
+    // restore the terminal before any error is returned, the cursor stays hidden otherwise
+    term.clear_last_lines(drawn_lines)?;
+    term.show_cursor()?;
+
+    match selection? {
+        Some(selection) => {
+            term.write_line(&format!(
+                "{} {} {} {}",
+                style("✔").green(),
+                style(prompt).bold(),
+                style("·").dim(),
+                style(&files[selection].name).green()
+            ))?;
+            Ok(selection)
+        }
+        None => Err(Error::new(ErrorKind::Interrupted, "Aborted").into()),
+    }
+}
+
+/// Like `shc_file_input` without the search, but lets the user pick several files,
+/// space toggles a file and `a` toggles all of them.
+pub fn shc_file_multi_input(
+    files: &[ShcFile],
+    prompt: &str,
+    api_client: &ApiClient,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    if !Term::stderr().is_term() {
+        return Err(
+            Error::other("Cannot pick files without a terminal, pass a filter instead").into(),
+        );
+    }
+
+    // leave room for the checkbox dialoguer draws in front of each row
+    let table = FileTable::new(files, api_client, 6);
+    let files = files
+        .iter()
+        .map(|file| table.row(file))
+        .collect::<Vec<String>>();
+
+    Ok(MultiSelect::with_theme(&theme::ColorfulTheme::default())
         .max_length(20)
-        .with_prompt(prompt)
-        .default(0)
+        .with_prompt(format!("{} (space to select, a to select all)", prompt))
         .items(&files)
-        .interact()
-        .unwrap();
+        .interact()?)
+}
+
+/// Asks "Are you sure?" unless confirmations are turned off in the preferences.
+pub fn confirm_action(preferences: &Preferences) -> Result<bool, Box<dyn std::error::Error>> {
+    if !preferences.confirm() {
+        return Ok(true);
+    }
+    if !Term::stderr().is_term() {
+        return Err(Error::other(
+            "Cannot ask for confirmation without a terminal, turn it off with `shc config set confirm false`",
+        )
+        .into());
+    }
+
+    Ok(Confirm::new()
+        .with_prompt("Are you sure?")
+        .default(false)
+        .interact()?)
+}
+
+/// Finds the file `target` refers to: an exact id or name match, the only search
+/// result, or the one picked from the search results.
+pub async fn resolve_shc_file(
+    target: &str,
+    prompt: &str,
+    api_client: &mut ApiClient,
+) -> Result<Option<ShcFile>, Box<dyn std::error::Error>> {
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message("Fetching files...");
+    let mut res = api_client.list_files(target).await?;
+    pb.finish_and_clear();
+
+    if res.results.is_empty() {
+        if target.is_empty() {
+            println!("No files found.");
+        } else {
+            println!("No files found matching \"{}\".", target);
+        }
+        return Ok(None);
+    }
+
+    let selection = match res
+        .results
+        .iter()
+        .position(|file| file.id == target || file.name == target)
+    {
+        Some(position) => position,
+        None if res.results.len() == 1 => 0,
+        None => shc_file_input(&mut res.results, prompt, api_client).await?,
+    };
+
+    Ok(Some(res.results.swap_remove(selection)))
+}
+
+/// Appended to the message of a transfer bar when transfers are rate limited.
+pub fn rate_limit_note(rate_limiter: Option<&RateLimiter>, size_format: SizeFormat) -> String {
+    match rate_limiter {
+        Some(rate_limiter) => format!(
+            " (limited to {}/s)",
+            size_format.format(rate_limiter.bytes_per_sec())
+        ),
+        None => String::new(),
+    }
+}
+
+/// Style of upload and download progress bars, sizes follow the user's size format.
+pub fn transfer_progress_style(size_format: SizeFormat) -> ProgressStyle {
+    ProgressStyle::with_template(
+        "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {size}/{total_size} ({eta}) {rate} \n",
+    )
+    .unwrap()
+    .with_key(
+        "size",
+        move |state: &ProgressState, w: &mut dyn fmt::Write| {
+            write!(w, "{}", size_format.format(state.pos())).unwrap()
+        },
+    )
+    .with_key(
+        "total_size",
+        move |state: &ProgressState, w: &mut dyn fmt::Write| {
+            write!(w, "{}", size_format.format(state.len().unwrap_or(0))).unwrap()
+        },
+    )
+    .with_key(
+        "rate",
+        move |state: &ProgressState, w: &mut dyn fmt::Write| {
+            write!(w, "{}/s", size_format.format(state.per_sec() as u64)).unwrap()
+        },
+    )
+    .progress_chars("#>-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_middle_keeps_names_that_fit() {
+        assert_eq!(
+            truncate_middle("report.pdf", 10),
+            ("report.pdf".to_string(), 10, 10)
+        );
+        assert_eq!(
+            truncate_middle("日本語.txt", 10),
+            ("日本語.txt".to_string(), 7, 7)
+        );
+    }
+
+    #[test]
+    fn truncate_middle_cuts_multi_byte_names_on_char_boundaries() {
+        let (text, head_len, tail_start) = truncate_middle("résumé-final-v2.pdf", 9);
+        assert_eq!(text, "résu….pdf");
+        assert_eq!((head_len, tail_start), (4, 15));
+        assert_eq!(UnicodeWidthStr::width(text.as_str()), 9);
+    }
+
+    #[test]
+    fn truncate_middle_counts_wide_chars_as_two_columns() {
+        let (text, head_len, tail_start) = truncate_middle("日本語のファイル名.txt", 10);
+        assert_eq!(text, "日本….txt");
+        assert_eq!((head_len, tail_start), (2, 9));
+        assert!(UnicodeWidthStr::width(text.as_str()) <= 10);
 
-    selection
+        let (text, _, _) = truncate_middle("ファイル名ファイル名", 9);
+        assert_eq!(text, "ファ…ル名");
+        assert_eq!(UnicodeWidthStr::width(text.as_str()), 9);
+    }
 }
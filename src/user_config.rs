@@ -1,9 +1,14 @@
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::io::{Error, ErrorKind, Write};
+use std::{env, fs, path::Path, path::PathBuf};
+use tempfile::NamedTempFile;
 
-use crate::consts::{SHC_CLI_FOLDER_NAME, USER_CONFIG_FILE_NAME};
+use crate::consts::{
+    CONFIG_VERSION, SHC_CLI_FOLDER_NAME, SHC_CONFIG_ENV, SHC_XDG_FOLDER_NAME, USER_CONFIG_FILE_NAME,
+};
+use crate::preferences::Preferences;
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
 pub struct UserInfo {
     pub email: Option<String>,
     pub name: Option<String>,
@@ -12,59 +17,393 @@ pub struct UserInfo {
     pub refresh_token: Option<String>,
 }
 
+impl UserInfo {
+    pub fn has_credentials(&self) -> bool {
+        self.access_token.is_some() || self.refresh_token.is_some()
+    }
+}
+
+/// On-disk layout of the config file.
+#[derive(Deserialize, Serialize)]
+struct ConfigFile {
+    version: u32,
+    #[serde(default)]
+    user: UserInfo,
+    #[serde(default)]
+    preferences: Preferences,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 
 pub struct UserConfig {
     pub user: UserInfo,
+    pub preferences: Preferences,
     pub config_path: PathBuf,
 }
 
 impl UserConfig {
-    pub fn new() -> Self {
-        let shc_folder = dirs::home_dir().unwrap().join(SHC_CLI_FOLDER_NAME);
-        let config_path = shc_folder.join(USER_CONFIG_FILE_NAME);
-        if !shc_folder.exists() {
-            std::fs::create_dir_all(&shc_folder).unwrap();
+    /// Loads the config from `config_path`, `$SHC_CONFIG` or the default location.
+    pub fn new(config_path: Option<PathBuf>) -> Result<Self, Box<dyn std::error::Error>> {
+        let config_path = match config_path {
+            Some(config_path) => config_path,
+            None => match env::var_os(SHC_CONFIG_ENV).filter(|path| !path.is_empty()) {
+                Some(config_path) => PathBuf::from(config_path),
+                None => Self::default_folder()?.join(USER_CONFIG_FILE_NAME),
+            },
+        };
+
+        if let Some(shc_folder) = config_path.parent() {
+            if !shc_folder.as_os_str().is_empty() {
+                fs::create_dir_all(shc_folder)?;
+            }
         }
+
         if !config_path.exists() {
             let user_config = UserConfig {
-                user: UserInfo {
-                    email: None,
-                    name: None,
-                    user_id: None,
-                    access_token: None,
-                    refresh_token: None,
-                },
-                config_path: config_path.clone(),
+                user: UserInfo::default(),
+                preferences: Preferences::default(),
+                config_path,
             };
-            user_config.save();
-            return user_config;
+            user_config.save()?;
+            return Ok(user_config);
+        }
+
+        Self::from_path(&config_path)
+    }
+
+    pub fn from_path(config_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut user_config = UserConfig {
+            user: UserInfo::default(),
+            preferences: Preferences::default(),
+            config_path: config_path.to_path_buf(),
+        };
+
+        // only content that can't be parsed is repaired, anything else (permissions,
+        // a folder in the way) is the user's to fix and must not cost them their config
+        let contents = match fs::read_to_string(config_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::InvalidData => {
+                user_config.repair(&e.to_string())?;
+                return Ok(user_config);
+            }
+            Err(e) => {
+                return Err(Error::new(
+                    e.kind(),
+                    format!("Could not read {}: {}", config_path.display(), e),
+                )
+                .into())
+            }
+        };
+        let table = match contents.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(e) => {
+                user_config.repair(&e.to_string())?;
+                return Ok(user_config);
+            }
+        };
+
+        let version = match table.get("version") {
+            None => 0,
+            Some(version) => match version.as_integer() {
+                Some(version) if version >= 0 => version as u32,
+                _ => {
+                    user_config.repair("invalid version")?;
+                    return Ok(user_config);
+                }
+            },
+        };
+        if version > CONFIG_VERSION {
+            return Err(Error::other(format!(
+                "{} was written by a newer version of shc (config version {})",
+                config_path.display(),
+                version
+            ))
+            .into());
         }
 
-        let contents =
-            fs::read_to_string(&config_path).expect("Something went wrong reading the file");
+        let mut table = migrate(table, version);
+
+        // a bad preference shouldn't cost the user their login, so it's handled apart from the rest
+        let preferences = match table.remove("preferences") {
+            Some(toml::Value::Table(preferences)) => Preferences::from_table(preferences),
+            Some(_) => Err(Error::other("preferences must be a table").into()),
+            None => Ok(Preferences::default()),
+        };
+        match preferences {
+            Ok(preferences) => user_config.preferences = preferences,
+            Err(e) => eprintln!(
+                "Ignoring invalid preferences in {} ({}), fix them with `shc config edit`",
+                config_path.display(),
+                e.to_string().lines().next().unwrap_or_default().trim()
+            ),
+        }
 
-        let user: UserInfo = toml::from_str(&contents).expect("Could not parse TOML");
-        UserConfig {
-            user,
-            config_path: config_path.clone(),
+        match table.try_into::<ConfigFile>() {
+            Ok(config_file) => {
+                user_config.user = config_file.user;
+                if version < CONFIG_VERSION {
+                    user_config.save()?;
+                }
+            }
+            Err(e) => user_config.repair(&e.to_string())?,
         }
+
+        Ok(user_config)
     }
 
-    pub fn save(&self) {
-        let toml = toml::to_string(&self.user).unwrap();
-        fs::write(&self.config_path, toml).unwrap();
+    /// `$XDG_CONFIG_HOME/shc-cli` when set, `~/.shc-cli` otherwise.
+    /// An existing `~/.shc-cli` keeps being used so setting XDG_CONFIG_HOME doesn't log anyone out.
+    fn default_folder() -> Result<PathBuf, Error> {
+        let legacy_folder = dirs::home_dir().map(|home_dir| home_dir.join(SHC_CLI_FOLDER_NAME));
+        if let Some(xdg_config_home) = env::var_os("XDG_CONFIG_HOME").filter(|p| !p.is_empty()) {
+            let xdg_folder = PathBuf::from(xdg_config_home).join(SHC_XDG_FOLDER_NAME);
+            match legacy_folder {
+                Some(legacy_folder) if !xdg_folder.exists() && legacy_folder.exists() => {
+                    return Ok(legacy_folder)
+                }
+                _ => return Ok(xdg_folder),
+            }
+        }
+        match legacy_folder {
+            Some(legacy_folder) => Ok(legacy_folder),
+            None => Err(Error::other(format!(
+                "Could not determine home directory, set {} or use --config",
+                SHC_CONFIG_ENV
+            ))),
+        }
+    }
+
+    /// This config and every other profile config stored next to it. Other
+    /// `*.toml` files in the folder are left alone unless they are shc configs.
+    pub fn all_profiles(&self) -> Result<Vec<UserConfig>, Box<dyn std::error::Error>> {
+        let shc_folder = match self.config_path.parent() {
+            Some(shc_folder) if !shc_folder.as_os_str().is_empty() => shc_folder,
+            _ => Path::new("."),
+        };
+
+        let mut paths = fs::read_dir(shc_folder)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path.extension().is_some_and(|ext| ext == "toml")
+                    && !same_file(path, &self.config_path)
+                    && is_profile(path)
+            })
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+
+        let mut profiles = vec![self.clone()];
+        for path in paths {
+            profiles.push(Self::from_path(&path)?);
+        }
+        Ok(profiles)
+    }
+
+    /// Moves a corrupt config aside and starts over with an empty one.
+    fn repair(&mut self, reason: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let backup_path = self.config_path.with_extension(format!(
+            "toml.bak-{}",
+            chrono::Local::now().format("%Y%m%d%H%M%S")
+        ));
+        fs::rename(&self.config_path, &backup_path)?;
+        eprintln!(
+            "Config {} is corrupt ({}), moved it to {}",
+            self.config_path.display(),
+            reason.lines().next().unwrap_or_default().trim(),
+            backup_path.display()
+        );
+
+        self.user = UserInfo::default();
+        self.save()
     }
 
-    // FIXME: not working
-    pub fn clear(&mut self) {
-        self.user = UserInfo {
-            email: None,
-            name: None,
-            user_id: None,
-            access_token: None,
-            refresh_token: None,
+    /// Writes to a temp file and renames it over the config so readers never see a partial file.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let config_file = ConfigFile {
+            version: CONFIG_VERSION,
+            user: self.user.clone(),
+            preferences: self.preferences.clone(),
         };
-        self.save();
+        let toml = toml::to_string(&config_file)?;
+
+        let shc_folder = match self.config_path.parent() {
+            Some(shc_folder) if !shc_folder.as_os_str().is_empty() => shc_folder,
+            _ => Path::new("."),
+        };
+        let mut file = NamedTempFile::new_in(shc_folder)?;
+        file.write_all(toml.as_bytes())?;
+        file.as_file().sync_all()?;
+        file.persist(&self.config_path)?;
+        Ok(())
+    }
+
+    /// Keys of encrypted uploads, one keystore per profile.
+    pub fn keystore_path(&self) -> PathBuf {
+        self.config_path.with_extension("keys")
+    }
+
+    /// Wipes the stored credentials and returns what was removed.
+    pub fn clear(&mut self) -> Result<UserInfo, Box<dyn std::error::Error>> {
+        let removed = std::mem::take(&mut self.user);
+        self.save()?;
+        Ok(removed)
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether `path` holds a current shc config: a supported `version` and a
+/// valid `[user]` table. Nothing else gets migrated, repaired or rewritten.
+fn is_profile(path: &Path) -> bool {
+    let table = match fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| contents.parse::<toml::Table>().ok())
+    {
+        Some(table) => table,
+        None => return false,
+    };
+    let version_ok = table
+        .get("version")
+        .and_then(|version| version.as_integer())
+        .is_some_and(|version| (1..=CONFIG_VERSION as i64).contains(&version));
+    let user_ok = match table.get("user") {
+        Some(toml::Value::Table(user)) => user.clone().try_into::<UserInfo>().is_ok(),
+        _ => false,
+    };
+    version_ok && user_ok
+}
+
+/// Upgrades a config table from `version` to `CONFIG_VERSION` one step at a time.
+fn migrate(mut table: toml::Table, version: u32) -> toml::Table {
+    if version < 1 {
+        // v0 stored the user fields at the top level
+        let mut user = toml::Table::new();
+        for key in ["email", "name", "user_id", "access_token", "refresh_token"] {
+            if let Some(value) = table.remove(key) {
+                user.insert(key.to_string(), value);
+            }
+        }
+        table.insert("user".to_string(), toml::Value::Table(user));
+    }
+
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(CONFIG_VERSION as i64),
+    );
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_v0_user_fields_into_user_table() {
+        let v0 = r#"
+            email = "ada@example.com"
+            name = "Ada"
+            access_token = "access"
+            refresh_token = "refresh"
+        "#
+        .parse::<toml::Table>()
+        .unwrap();
+
+        let table = migrate(v0, 0);
+        assert_eq!(table["version"].as_integer(), Some(CONFIG_VERSION as i64));
+        assert!(table.get("email").is_none());
+        let user = table["user"].as_table().unwrap();
+        assert_eq!(user["email"].as_str(), Some("ada@example.com"));
+        assert_eq!(user["access_token"].as_str(), Some("access"));
+        assert_eq!(user["refresh_token"].as_str(), Some("refresh"));
+        assert!(user.get("user_id").is_none());
+    }
+
+    #[test]
+    fn loading_v0_config_keeps_login_and_saves_v1() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "email = \"ada@example.com\"\naccess_token = \"access\"\n",
+        )
+        .unwrap();
+
+        let user_config = UserConfig::from_path(&path).unwrap();
+        assert_eq!(user_config.user.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(user_config.user.access_token.as_deref(), Some("access"));
+
+        let saved = fs::read_to_string(&path)
+            .unwrap()
+            .parse::<toml::Table>()
+            .unwrap();
+        assert_eq!(saved["version"].as_integer(), Some(CONFIG_VERSION as i64));
+        assert!(saved["user"].as_table().is_some());
+    }
+
+    #[test]
+    fn repairs_unparsable_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "[user\nbroken").unwrap();
+
+        let user_config = UserConfig::from_path(&path).unwrap();
+        assert!(!user_config.user.has_credentials());
+        let backups = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("config.toml.bak-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+
+    #[test]
+    fn read_errors_leave_config_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profile");
+        fs::create_dir(&path).unwrap();
+
+        assert!(UserConfig::from_path(&path).is_err());
+        assert!(path.is_dir());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn all_profiles_skips_other_toml_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cargo_toml = "[package]\nname = \"x\"\nversion = \"0.1.0\"\n";
+        fs::write(dir.path().join("Cargo.toml"), cargo_toml).unwrap();
+        fs::write(dir.path().join("pyproject.toml"), "[tool\nbroken").unwrap();
+        fs::write(
+            dir.path().join("work.toml"),
+            "version = 1\n[user]\nemail = \"ada@example.com\"\n",
+        )
+        .unwrap();
+
+        let user_config = UserConfig::new(Some(dir.path().join("config.toml"))).unwrap();
+        let profiles = user_config.all_profiles().unwrap();
+        let names = profiles
+            .iter()
+            .map(|profile| profile.config_path.file_name().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["config.toml", "work.toml"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+            cargo_toml
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("pyproject.toml")).unwrap(),
+            "[tool\nbroken"
+        );
     }
 }
@@ -1,38 +1,100 @@
-use ignore::WalkBuilder;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use ignore::{Walk, WalkBuilder};
 use std::fs::{self, File};
-use std::io::{self};
+use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use zip::write::FileOptions;
-use zip::{CompressionMethod::Bzip2, ZipWriter};
+use zip::{CompressionMethod, ZipWriter};
 
 use crate::consts::SHC_IGNORE_FILE_NAME;
 use tempfile::NamedTempFile;
 
-pub fn format_bytes(bytes: u64) -> String {
-    let mut bytes = bytes as f64;
-    let mut unit = 0;
+/// Fails a batch command once its results are printed if any of `total` items failed,
+/// so scripts notice. `items` names what failed, e.g. "downloads".
+pub fn batch_result(failed: usize, total: usize, items: &str) -> io::Result<()> {
+    if failed == 0 {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "{} of {} {} failed",
+        failed, total, items
+    )))
+}
 
-    while bytes >= 1000.0 {
-        bytes /= 1000.0;
-        unit += 1;
+/// Parses `2024-01-31`, an RFC 3339 timestamp or a duration like `7d` meaning that long ago.
+pub fn parse_date(input: &str) -> Result<DateTime<Utc>, String> {
+    let input = input.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(input) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|date| date.and_local_timezone(Local).earliest())
+            .map(|date| date.with_timezone(&Utc))
+            .ok_or_else(|| format!("Invalid date \"{}\"", input));
     }
 
-    let unit = match unit {
-        0 => "B",
-        1 => "KB",
-        2 => "MB",
-        3 => "GB",
-        4 => "TB",
-        5 => "PB",
-        6 => "EB",
-        7 => "ZB",
-        _ => "YB",
+    let duration = parse_duration(input).map_err(|_| {
+        format!(
+            "Invalid date \"{}\", expected e.g. 2024-01-31 or 7d (7 days ago)",
+            input
+        )
+    })?;
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| Utc::now().checked_sub_signed(duration))
+        .ok_or_else(|| format!("Invalid date \"{}\"", input))
+}
+
+/// Parses durations like `90s`, `30m`, `24h`, `7d` or `2w`.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let unit_start = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(unit_start);
+
+    let amount = amount.parse::<u64>().map_err(|_| {
+        format!(
+            "Invalid duration \"{}\", expected e.g. 30m, 24h or 7d",
+            input
+        )
+    })?;
+    let seconds = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit \"{}\", expected s, m, h, d or w",
+                unit
+            ))
+        }
     };
 
-    format!("{:.2} {}", bytes, unit)
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration \"{}\" is too long", input))
+}
+
+/// Walks the files of a folder upload, skipping what `.shcignore` excludes.
+pub fn walk_upload_dir(path: &Path) -> Walk {
+    WalkBuilder::new(path)
+        .git_ignore(false)
+        .add_custom_ignore_filename(SHC_IGNORE_FILE_NAME)
+        .build()
 }
 
-pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<PathBuf> {
+pub fn zip_directory_recursive(
+    src_dir: &Path,
+    size_limit: u64,
+    compression_method: CompressionMethod,
+) -> io::Result<PathBuf> {
     let src_dir = fs::canonicalize(src_dir)?;
     let folder_name = src_dir.file_name().unwrap().to_string_lossy();
     let dest_file_path = NamedTempFile::new_in("/tmp")?
@@ -49,6 +111,7 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
         zip: &mut ZipWriter<File>,
         base_path: &Path,
         size_limit: u64,
+        compression_method: CompressionMethod,
         current_size: &mut u64,
     ) -> io::Result<u64> {
         let mut total_size = 0;
@@ -57,7 +120,7 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
             let relative_path = path.strip_prefix(base_path).unwrap();
             let zip_path = relative_path.to_string_lossy();
             let options = FileOptions::default()
-                .compression_method(Bzip2)
+                .compression_method(compression_method)
                 .unix_permissions(0o755);
 
             zip.start_file(zip_path, options)?;
@@ -68,18 +131,10 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
             *current_size += file_size;
 
             if *current_size > size_limit {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Exceeded size limit for zip file",
-                ));
+                return Err(io::Error::other("Exceeded size limit for zip file"));
             }
         } else if path.is_dir() {
-            let walker = WalkBuilder::new(path)
-                .git_ignore(false)
-                .add_custom_ignore_filename(SHC_IGNORE_FILE_NAME)
-                .build();
-
-            for result in walker {
+            for result in walk_upload_dir(path) {
                 // TODO: Handle errors
                 let entry_path = result.unwrap().into_path();
 
@@ -88,7 +143,14 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
                     continue;
                 }
 
-                total_size += zip_inner(&entry_path, zip, base_path, size_limit, current_size)?;
+                total_size += zip_inner(
+                    &entry_path,
+                    zip,
+                    base_path,
+                    size_limit,
+                    compression_method,
+                    current_size,
+                )?;
             }
         }
 
@@ -97,6 +159,13 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
 
     let mut current_size = 0;
 
-    let _total_size = zip_inner(&src_dir, &mut zip, &src_dir, size_limit, &mut current_size)?;
+    let _total_size = zip_inner(
+        &src_dir,
+        &mut zip,
+        &src_dir,
+        size_limit,
+        compression_method,
+        &mut current_size,
+    )?;
     Ok(dest_file_path)
 }